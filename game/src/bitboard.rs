@@ -0,0 +1,279 @@
+//pub mod generic;
+use generic::{Game,GameError,Move,Player,Score};
+use connectfour::{Column,ConnectFour,ConnectFourMove,STANDARD_WIDTH,STANDARD_HEIGHT,STANDARD_WIN_LENGTH};
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+//#################################################################################################
+// Connect Four on bitboards
+//#################################################################################################
+
+// bitboards are for the standard board only, a wider one would not fit into 64 bits
+const WIDTH: usize = STANDARD_WIDTH;
+const HEIGHT: usize = STANDARD_HEIGHT;
+
+// each column takes HEIGHT+1 bits, bottom up, column One in the lowest bits.
+// the extra bit on top of each column is never set, it keeps lines that are
+// shifted across the border of a column from wrapping into the next one.
+//
+//  .  .  .  .  .  .  .
+//  5 12 19 26 33 40 47
+//  4 11 18 25 32 39 46
+//  3 10 17 24 31 38 45
+//  2  9 16 23 30 37 44
+//  1  8 15 22 29 36 43
+//  0  7 14 21 28 35 42
+const STRIDE: usize = HEIGHT + 1;
+// the bottom cell of every column
+const BOTTOM: u64 = ((1 << (WIDTH * STRIDE)) - 1) / ((1 << STRIDE) - 1);
+// every cell of the field
+const FULL: u64 = BOTTOM * ((1 << HEIGHT) - 1);
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct BitBoard {
+    // stones of Black and White, a gray stone is marked on both boards
+    boards: [u64; 2],
+}
+
+fn index(p: &Player) -> usize {
+    match p {
+        Player::Black => 0,
+        Player::White => 1,
+        Player::Gray => panic!("gray has no board of its own"),
+    }
+}
+
+pub fn column_mask(n: usize) -> u64 {
+    ((1 << HEIGHT) - 1) << (n * STRIDE)
+}
+
+fn connects_four(b: u64) -> bool {
+    // vertical, '\', horizontal, '/'
+    for shift in [1, STRIDE - 1, STRIDE, STRIDE + 1].iter() {
+        let pairs = b & (b >> shift);
+        if pairs & (pairs >> (2 * shift)) != 0 {
+            return true;
+        }
+    }
+    false
+}
+
+impl Game<Column,[u64;2]> for BitBoard {
+    fn possible_moves(&self, _: &Player) -> Vec<Arc<dyn Move<Column>>> {
+        let mut allowed: Vec<Arc<dyn Move<Column>>> = Vec::new();
+        for n in 0..WIDTH {
+            if self.column_height(n) < HEIGHT {
+                allowed.push(Arc::new(ConnectFourMove {
+                    data: Column::from_usize(n).unwrap()
+                }));
+            }
+        }
+        allowed
+    }
+
+    fn make_move(&mut self, p: &Player, mv: Arc<dyn Move<Column>>) -> Result<Score, GameError> {
+        let n = mv.data().to_usize();
+        if n >= WIDTH {
+            return Err(GameError::InvalidColumn(n));
+        }
+        if self.column_height(n) == HEIGHT {
+            // column is already filled to the top
+            return Err(GameError::ColumnFull);
+        }
+
+        // drop the stone
+        self.play(p, n);
+
+        // return the score
+        if connects_four(self.stones(p)) {
+            return Ok(Score::Won(0));
+        }
+        if self.occupied() == FULL {
+            return Ok(Score::Remis(0));
+        }
+        Ok(Score::Undecided(0.5))
+    }
+
+    fn withdraw_move(&mut self, _p: &Player, mv: Arc<dyn Move<Column>>) -> Result<(), GameError> {
+        let n = mv.data().to_usize();
+        if n >= WIDTH {
+            return Err(GameError::InvalidColumn(n));
+        }
+        if self.column_height(n) == 0 {
+            return Err(GameError::EmptyColumn);
+        }
+        // un-drop the stone
+        self.unplay(n);
+        Ok(())
+    }
+
+    fn display(&self) -> String {
+        let mut s = String::new();
+        s.push_str("------\n");
+        for n in 0..WIDTH {
+            for m in 0..self.column_height(n) {
+                s.push(match self.cell(n, m) {
+                    Some(Player::White) => 'o',
+                    Some(Player::Black) => 'x',
+                    _ => ':',
+                });
+            }
+            s.push('\n');
+        }
+        s.push_str("------");
+        s
+    }
+
+    fn state(&self) -> &[u64;2] {
+        &self.boards
+    }
+
+    fn dimensions(&self) -> (usize, usize) {
+        (WIDTH, HEIGHT)
+    }
+
+    // the shifts in connects_four and winning_cells look for four in a row
+    fn win_length(&self) -> usize {
+        STANDARD_WIN_LENGTH
+    }
+}
+
+//### bitboard ####################################################################################
+
+impl BitBoard {
+    pub fn new() -> Self {
+        BitBoard { boards: [0, 0] }
+    }
+
+    pub fn from_state(state: &[Vec<Option<Player>>]) -> Self {
+        let mut bb = BitBoard::new();
+        for (n, col) in state.iter().enumerate() {
+            for (m, cell) in col.iter().enumerate() {
+                let stone = 1 << (n * STRIDE + m);
+                match cell {
+                    Some(Player::Black) => { bb.boards[0] |= stone; },
+                    Some(Player::White) => { bb.boards[1] |= stone; },
+                    Some(Player::Gray) => { bb.boards[0] |= stone; bb.boards[1] |= stone; },
+                    None => (),
+                }
+            }
+        }
+        bb
+    }
+
+    pub fn dropped_stones(&self) -> usize {
+        self.occupied().count_ones() as usize
+    }
+
+    pub fn drop_stone(&mut self, p: &Player, c: Column) -> Result<Score, GameError> {
+        self.make_move(p, Arc::new(ConnectFourMove { data: c }))
+    }
+
+    pub fn undrop_stone(&mut self, p: &Player, c: Column) -> Result<(), GameError> {
+        self.withdraw_move(p, Arc::new(ConnectFourMove { data: c }))
+    }
+
+    pub fn column_height(&self, n: usize) -> usize {
+        (self.occupied() & column_mask(n)).count_ones() as usize
+    }
+
+    // the stones that count for the player, gray stones count for nobody
+    pub fn stones(&self, p: &Player) -> u64 {
+        match p {
+            Player::Gray => self.boards[0] & self.boards[1],
+            player => self.boards[index(player)] & !self.boards[1 - index(player)],
+        }
+    }
+
+    pub fn cell(&self, n: usize, m: usize) -> Option<Player> {
+        let bit = 1 << (n * STRIDE + m);
+        match (self.boards[0] & bit != 0, self.boards[1] & bit != 0) {
+            (true, true) => Some(Player::Gray),
+            (true, false) => Some(Player::Black),
+            (false, true) => Some(Player::White),
+            (false, false) => None,
+        }
+    }
+
+    pub fn occupied(&self) -> u64 {
+        self.boards[0] | self.boards[1]
+    }
+
+    // the cells where the next stone of each column would land
+    pub fn playable(&self) -> u64 {
+        (self.occupied() + BOTTOM) & FULL
+    }
+
+    // empty cells that would connect four for the player,
+    // whether they can be played right away or not
+    pub fn winning_cells(&self, p: &Player) -> u64 {
+        let s = self.stones(p);
+        // vertical
+        let mut r = (s << 1) & (s << 2) & (s << 3);
+        // '\', horizontal, '/'
+        for shift in [STRIDE - 1, STRIDE, STRIDE + 1].iter() {
+            let pairs = (s << shift) & (s << (2 * shift));
+            r |= pairs & (s << (3 * shift));
+            r |= pairs & (s >> shift);
+            let pairs = (s >> shift) & (s >> (2 * shift));
+            r |= pairs & (s << shift);
+            r |= pairs & (s >> (3 * shift));
+        }
+        r & (FULL ^ self.occupied())
+    }
+
+    pub fn connects_four(&self, p: &Player) -> bool {
+        connects_four(self.stones(p))
+    }
+
+    // drop a stone without any checks, the column must not be full
+    pub fn play(&mut self, p: &Player, n: usize) {
+        let stone = self.playable() & column_mask(n);
+        match p {
+            Player::Gray => { self.boards[0] |= stone; self.boards[1] |= stone; },
+            player => { self.boards[index(player)] |= stone; },
+        }
+    }
+
+    // remove the top stone of a column without any checks, the column must not be empty
+    pub fn unplay(&mut self, n: usize) {
+        let stone = !(1 << (n * STRIDE + self.column_height(n) - 1));
+        self.boards[0] &= stone;
+        self.boards[1] &= stone;
+    }
+
+    pub fn width() -> usize { WIDTH }
+    pub fn height() -> usize { HEIGHT }
+}
+
+impl Default for BitBoard {
+    fn default() -> Self {
+        BitBoard::new()
+    }
+}
+
+// only boards of the standard dimensions and rules fit into a bitboard
+impl<'a> TryFrom<&'a ConnectFour> for BitBoard {
+    type Error = GameError;
+
+    fn try_from(game: &'a ConnectFour) -> Result<Self, GameError> {
+        if game.dimensions() != (WIDTH, HEIGHT) || game.win_length() != STANDARD_WIN_LENGTH {
+            return Err(GameError::UnsupportedRules);
+        }
+        Ok(BitBoard::from_state(game.state()))
+    }
+}
+
+impl<'a> From<&'a BitBoard> for ConnectFour {
+    fn from(bb: &'a BitBoard) -> Self {
+        let mut game = ConnectFour::new();
+        for n in 0..WIDTH {
+            for m in 0..bb.column_height(n) {
+                // a stone in the cell is guaranteed by the column height
+                let p = bb.cell(n, m).unwrap();
+                game.drop_stone(&p, Column::from_usize(n).unwrap()).unwrap();
+            }
+        }
+        game
+    }
+}
@@ -0,0 +1,200 @@
+//pub mod generic;
+use generic::{Analysis,Game,GameError,Move,MoveKind,Player,Score,Strategy};
+use connectfour::{Column,ConnectFour,ConnectFourMove};
+use bruteforce::{MAX_CELLS,StoredGame,read_store,swap,write_store};
+use position::PositionKey;
+use std::collections::{HashMap,HashSet};
+use std::path::Path;
+use std::sync::{Arc,RwLock};
+use std::time::Duration;
+
+//#################################################################################################
+// opening book
+//#################################################################################################
+
+// the best moves of the positions of the first plies, found once and for all by another strategy.
+// a book is kept in a file like the game store, only that every game is keyed by its position.
+pub struct OpeningBook {
+    // width, height and win length of the board
+    rules: (usize,usize,usize),
+    // the column as seen from the side the key was taken from
+    entries: HashMap<(PositionKey, Player), (Score, Column)>,
+}
+
+impl OpeningBook {
+    pub fn new(rules: (usize,usize,usize)) -> Self {
+        OpeningBook { rules, entries: HashMap::new() }
+    }
+
+    pub fn rules(&self) -> (usize,usize,usize) {
+        self.rules
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // asks the strategy for the best move of every position up to the given number of stones,
+    // White moving first. positions the strategy leaves undecided do not make it into the book
+    pub fn generate<X>(rules: (usize,usize,usize), plies: usize, strategy: &X, moves_ahead: i32)
+    -> Result<Self, GameError>
+    where X: Strategy<Column,Vec<Vec<Option<Player>>>> + ?Sized {
+        let (width, height, win_length) = rules;
+        if width * height > MAX_CELLS {
+            return Err(GameError::InvalidDimensions(width, height));
+        }
+        let mut book = OpeningBook::new(rules);
+        let mut level = vec![ConnectFour::with_rules(width, height, win_length)?];
+        let mut p = Player::White;
+        for ply in 0..=plies {
+            // a position and its mirror image are looked into once
+            let mut seen = HashSet::new();
+            let mut next_level = Vec::new();
+            for game in level {
+                let g = Arc::new(RwLock::new(game.clone()));
+                if let (Some(mv), Some(score)) = strategy.find_best_move(g, &p, moves_ahead, false) {
+                    if mv.kind() == MoveKind::Drop && !matches!(score, Score::Undecided(_)) {
+                        book.insert(&game, &p, mv.data(), score);
+                    }
+                }
+                if ply == plies {
+                    continue;
+                }
+                for mv in game.possible_moves(&p) {
+                    let mut next = game.clone();
+                    // a decided game is no opening any more
+                    if let Ok(Score::Undecided(_)) = next.make_move(&p, mv) {
+                        if seen.insert(PositionKey::of(&next).unwrap().0) {
+                            next_level.push(next);
+                        }
+                    }
+                }
+            }
+            level = next_level;
+            p = p.opponent().clone();
+        }
+        Ok(book)
+    }
+
+    fn insert(&mut self, game: &ConnectFour, p: &Player, column: &Column, score: Score) {
+        let (key, mirrored) = PositionKey::of(game).unwrap();
+        self.entries.insert((key, p.clone()), (score, swap(column, mirrored, self.rules.0)));
+    }
+
+    pub fn read(path: &Path, rules: (usize,usize,usize)) -> Result<Self, std::io::Error> {
+        let mut book = OpeningBook::new(rules);
+        for game in read_store(path, rules)? {
+            let key = PositionKey::from_hash(game.hash, rules)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            if let Some(column) = game.column {
+                book.entries.insert((key, game.player), (game.score, column));
+            }
+        }
+        Ok(book)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), std::io::Error> {
+        let mut games: Vec<StoredGame> = self.entries.iter().map(|((key, player), (score, column))| {
+            StoredGame { hash: key.hash(), player: player.clone(), score: score.clone(), column: Some(column.clone()) }
+        }).collect();
+        // the same book makes the same file
+        games.sort_by_key(|game| (game.hash, game.player == Player::White));
+        write_store(path, self.rules, &games)
+    }
+}
+
+impl PositionTable for OpeningBook {
+    fn probe(&self, game: &dyn Game<Column,Vec<Vec<Option<Player>>>>, p: &Player) -> Option<(Column, Score)> {
+        let (width, height, win_length) = self.rules;
+        if game.dimensions() != (width, height) || game.win_length() != win_length || game.pop_out() {
+            return None;
+        }
+        let (key, mirrored) = PositionKey::of_game(game)?;
+        self.entries.get(&(key, p.clone())).map(|(score, column)| {
+            (swap(column, mirrored, width), score.clone())
+        })
+    }
+}
+
+//### book strategy ###############################################################################
+
+// positions whose best moves are known without searching, like the ones of an opening book
+pub trait PositionTable: Send + Sync {
+    // the best move of the player and its score, if the position is in the table
+    fn probe(&self, game: &dyn Game<Column,Vec<Vec<Option<Player>>>>, p: &Player) -> Option<(Column, Score)>;
+}
+
+// plays from the book as long as the position is in there and leaves the rest to another strategy.
+// the book may be any table of positions, an endgame table as well
+pub struct BookStrategy<X> {
+    pub book: Arc<dyn PositionTable>,
+    pub strategy: X,
+}
+
+impl<X> BookStrategy<X> where X: Strategy<Column,Vec<Vec<Option<Player>>>> {
+    pub fn new(book: Arc<dyn PositionTable>, strategy: X) -> Self {
+        BookStrategy { book, strategy }
+    }
+}
+
+impl<X> Strategy<Column,Vec<Vec<Option<Player>>>> for BookStrategy<X>
+where X: Strategy<Column,Vec<Vec<Option<Player>>>> {
+    fn evaluate_move(&self, g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+                     p: &Player, mv: Arc<dyn Move<Column>>)
+    -> Result<f32, GameError> {
+        self.strategy.evaluate_move(g, p, mv)
+    }
+
+    fn threads(&self) -> usize {
+        self.strategy.threads()
+    }
+
+    fn find_best_move(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            moves_ahead: i32,
+            game_evaluation: bool
+        ) -> (Option<Arc<dyn Move<Column>>>, Option<Score>) {
+        // the game is not locked any more when the strategy searches
+        let book_move = self.book.probe(&*g.read().unwrap(), p);
+        match book_move {
+            Some((column, score)) => (Some(Arc::new(ConnectFourMove { data: column })), Some(score)),
+            None => self.strategy.find_best_move(g, p, moves_ahead, game_evaluation),
+        }
+    }
+
+    fn find_best_move_in_time(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            budget: Duration,
+            game_evaluation: bool
+        ) -> (Option<Arc<dyn Move<Column>>>, Option<Score>) {
+        let book_move = self.book.probe(&*g.read().unwrap(), p);
+        match book_move {
+            Some((column, score)) => (Some(Arc::new(ConnectFourMove { data: column })), Some(score)),
+            None => self.strategy.find_best_move_in_time(g, p, budget, game_evaluation),
+        }
+    }
+
+    fn analyze_position(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            moves_ahead: i32,
+            game_evaluation: bool
+        ) -> Vec<(Column, Score)> {
+        self.strategy.analyze_position(g, p, moves_ahead, game_evaluation)
+    }
+
+    fn analyze(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            moves_ahead: i32,
+            game_evaluation: bool
+        ) -> Analysis<Column> {
+        self.strategy.analyze(g, p, moves_ahead, game_evaluation)
+    }
+}
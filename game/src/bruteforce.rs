@@ -1,10 +1,13 @@
 //pub mod generic;
-use generic::{Game,Move,Player,Score,Strategy,Withdraw};
+use generic::{Analysis,Game,GameError,Move,MoveKind,Player,Score,Strategy,permissive,principal_variation,search_best_move,search_best_move_in_time};
 use connectfour::{Column,ConnectFour,ConnectFourMove,ConnectFourStrategy};
-use std::rc::Rc;
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::sync::{Arc,Mutex};
+use position::PositionKey;
+use book::PositionTable;
+use endgame::EndgameTable;
+use std::collections::{HashMap,HashSet};
+use std::cmp;
+use std::sync::{Arc,Mutex,RwLock};
+use std::sync::atomic::{AtomicBool,Ordering};
 use std::sync::mpsc::{channel,Sender,Receiver};
 use std::thread;
 use std::time::{Duration,Instant};
@@ -18,8 +21,12 @@ use std::time::{Duration,Instant};
 
 type GameHash = i128;
 
-pub fn hash_from_game(game:Rc<RefCell<dyn Game<Column,Vec<Vec<Option<Player>>>>>>) -> GameHash {
-    let (h,_s) = hash_from_state(game.borrow().state());
+// four stones make a digit, a game on a board of more cells than that has no hash
+pub const MAX_CELLS: usize = 63;
+
+pub fn hash_from_game(game:Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>) -> GameHash {
+    let game = game.read().unwrap();
+    let (h,_s) = hash_from_state(game.state(), game.dimensions().1);
     h
 }
 
@@ -48,7 +55,8 @@ fn bias(v:&Vec<Vec<Option<Player>>>) -> bool {
     false
 }
 
-pub fn hash_from_state(state:&Vec<Vec<Option<Player>>>) -> (GameHash, bool) {
+// every column takes height digits of the base 4 hash, the leftmost column the lowest ones
+pub fn hash_from_state(state:&Vec<Vec<Option<Player>>>, height:usize) -> (GameHash, bool) {
     let mut s = 0;
     let mut f = 1;
     let mut ci = 0;
@@ -56,8 +64,8 @@ pub fn hash_from_state(state:&Vec<Vec<Option<Player>>>) -> (GameHash, bool) {
     
     let swapped = bias(state);
     let oi:Vec<usize> = match swapped {
-        true => ConnectFour::walkdown(),
-        false => ConnectFour::walkup(),
+        true => (0..state.len()).rev().collect(),
+        false => (0..state.len()).collect(),
     };
     for i in oi {
         let c = &state[i];
@@ -73,38 +81,69 @@ pub fn hash_from_state(state:&Vec<Vec<Option<Player>>>) -> (GameHash, bool) {
             f *= base;
         }
         ci += 1;
-        f = base.pow(ConnectFour::height() as u32 * ci);
+        f = base.pow(height as u32 * ci);
     }
     (s,swapped)
 }
 
-fn game_from_hash(hash:GameHash) -> ConnectFour {
-    let mut game = ConnectFour::new();
+// the hash a game keeps move by move, the games of the store are small enough to have one
+fn hash_of(cf:&ConnectFour) -> (GameHash, bool) {
+    let (key, swapped) = PositionKey::of(cf).expect("the board is too large for a hash");
+    (key.hash(), swapped)
+}
+
+// the column as seen from the other side of the board, if the hash was taken from there
+pub fn swap(column:&Column, swapped:bool, width:usize)-> Column {
+    if swapped {
+        Column::from_usize(width - 1 - column.to_usize()).unwrap()
+    } else {
+        column.clone()
+    }
+}
+
+fn stones_in_hash(hash:GameHash) -> u32 {
     let mut h = hash;
-    let base:i128 = 4;
-    for ci in 0..ConnectFour::width() {
-        let col = Column::from_usize(ci);
-        let mut cr = h % base.pow(ConnectFour::height() as u32);
-        for _ri in 0..ConnectFour::height() {
-            let stone = cr % base;
-            match stone {
-                1 => { game.drop_stone(&Player::White, col.clone()).unwrap(); },
-                2 => { game.drop_stone(&Player::Black, col.clone()).unwrap(); },
-                3 => { game.drop_stone(&Player::Gray, col.clone()).unwrap(); },
-                0 => break,
-                _ => (),
+    let mut stones = 0;
+    while h > 0 {
+        if h % 4 != 0 { stones += 1; }
+        h /= 4;
+    }
+    stones
+}
+
+// the hashes of the games after a move of the player and the opponent's reply,
+// shaded the same way as in the two moves ahead inquiry
+fn two_moves_ahead(hash:GameHash, p:&Player, rules:(usize,usize,usize)) -> Vec<GameHash> {
+    let mut cf = game_from_hash(hash, rules);
+    let mut hashes = Vec::new();
+    for mv in cf.possible_moves(p) {
+        let (score, grayed_one) = match cf.make_shading_move(p, Arc::clone(&mv)) {
+            Ok(result) => result,
+            Err(_) => continue,
+        };
+        if let Score::Undecided(_) = score {
+            for anti_mv in cf.possible_moves(p.opponent()) {
+                if let Ok((_, grayed_two)) = cf.make_shading_move(p.opponent(), Arc::clone(&anti_mv)) {
+                    hashes.push(hash_of(&cf).0);
+                    cf.withdraw_move_unshading(p.opponent(), Arc::clone(&anti_mv), grayed_two);
+                }
             }
-            cr = cr / base;
         }
-        h = h / base.pow(ConnectFour::height() as u32);
+        cf.withdraw_move_unshading(p, Arc::clone(&mv), grayed_one);
     }
-    game
+    hashes
+}
+
+// the rules are width, height and win length of the board.
+// the hashes of the store are keys of positions, see PositionKey
+fn game_from_hash(hash:GameHash, rules:(usize,usize,usize)) -> ConnectFour {
+    PositionKey::decode(hash, rules).expect("the hash of a position")
 }
 
 pub struct GameRecord {
     state: GameState,
 }
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,PartialEq)]
 pub enum GameState {
     Locked,
     Decided(Score, Option<Column>),
@@ -112,8 +151,79 @@ pub enum GameState {
     Novel,
     Recall,
 }
-pub struct BruteForceStrategy {
+#[derive(Debug,Clone)]
+pub struct BruteForceConfig {
     pub nworkers: usize,
+    // a simulation goes one move deeper as long as the last depth took less than that
+    pub time_limit: Duration,
+    // once that many stones were dropped, games are simulated till the bitter end
+    pub basically_over: usize,
+    // text file the decided games are dumped to once the verdict is found
+    pub dump: Option<String>,
+    // progress is reported every progress_interval jobs
+    pub progress_interval: Option<u64>,
+    // games with enough stones for the table are looked up rather than simulated
+    pub endgame: Option<Arc<EndgameTable>>,
+}
+
+impl Default for BruteForceConfig {
+    fn default() -> Self {
+        BruteForceConfig {
+            nworkers: 1,
+            time_limit: Duration::from_millis(0),
+            basically_over: 30,
+            dump: Some(String::from(STRDMP)),
+            progress_interval: Some(1000000),
+            endgame: None,
+        }
+    }
+}
+
+pub struct BruteForceStrategy {
+    pub config: BruteForceConfig,
+    // file for keeping decided games from one run to the next
+    pub store: Option<String>,
+    // file for saving the state of the search every checkpoint_interval,
+    // an unfinished search of the same game is resumed from there
+    pub checkpoint: Option<String>,
+    pub checkpoint_interval: Duration,
+    // bytes the game store may take, beyond that records are evicted
+    pub memory_limit: Option<usize>,
+    // stops the search, find_best_move returns the best verdict found so far
+    pub cancellation: Option<CancellationToken>,
+    // called with the state of the search every progress_interval jobs
+    pub progress: Option<ProgressCallback>,
+}
+
+// a search can be cancelled from any thread through a clone of its token
+#[derive(Debug,Clone,Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+pub type ProgressCallback = Arc<dyn Fn(&Progress) + Send + Sync>;
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct Progress {
+    // records in the game store
+    pub records: usize,
+    // games other games are waiting for
+    pub interests: usize,
+    pub jobs: u64,
 }
 
 enum Cell {
@@ -126,8 +236,8 @@ enum Cell {
 impl BruteForceStrategy {
     #[allow(dead_code)]
     fn display_efield(&self, ef: &Vec<Vec<Cell>>) {
-        for j in (0..ConnectFour::height()).rev() {
-            for i in 0..ConnectFour::width() {
+        for j in (0..ef[0].len()).rev() {
+            for i in 0..ef.len() {
                 print!("{}", match ef[i][j] {
                     Cell::N => ".",
                     Cell::M => "m",
@@ -176,18 +286,20 @@ impl BruteForceStrategy {
 
 impl Strategy<Column,Vec<Vec<Option<Player>>>> for BruteForceStrategy {
     
-    fn evaluate_move(&self, g: Rc<RefCell<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
-                     p: &Player, mv: Rc<dyn Move<Column>>) 
-    -> Result<f32, Withdraw> {
+    fn evaluate_move(&self, g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+                     p: &Player, mv: Arc<dyn Move<Column>>) 
+    -> Result<f32, GameError> {
+        let (width, height) = g.read().unwrap().dimensions();
         let n = mv.data().to_usize();
-        let m = g.borrow().state()[n].len();
-        if m >= ConnectFour::height() { return Err(Withdraw::NotAllowed); }
+        if n >= width { return Err(GameError::InvalidColumn(n)); }
+        let m = g.read().unwrap().state()[n].len();
+        if m >= height && mv.kind() == MoveKind::Drop { return Err(GameError::ColumnFull); }
 
         // fill evaluation field with empty cells
-        let mut efield = Vec::with_capacity(ConnectFour::width());
-        for _ in 0..ConnectFour::width() {
-            let mut ecol = Vec::with_capacity(ConnectFour::height());
-            for _ in 0..ConnectFour::height() {
+        let mut efield = Vec::with_capacity(width);
+        for _ in 0..width {
+            let mut ecol = Vec::with_capacity(height);
+            for _ in 0..height {
                 ecol.push(Cell::N);
             }
             efield.push(ecol);
@@ -199,7 +311,7 @@ impl Strategy<Column,Vec<Vec<Option<Player>>>> for BruteForceStrategy {
         let white = |player: &Player| { match player {
             Player::White => Cell::M, Player::Black => Cell::O, Player::Gray => Cell::D, }};
         let mut i:usize = 0;
-        for c in g.borrow().state() { // that's the current Connect Four field
+        for c in g.read().unwrap().state() { // that's the current Connect Four field
             let mut j:usize = 0;
             for f in c {
                 match f {
@@ -217,32 +329,175 @@ impl Strategy<Column,Vec<Vec<Option<Player>>>> for BruteForceStrategy {
     }
 
     fn find_best_move(&self, 
-            g: Rc<RefCell<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
             p: &Player,
             moves_ahead: i32,
             // in the brute force context, game evaluation is irrelevant, 
             // because it we have our own find_best_move implementation
             _game_evaluation: bool
-        ) -> (Option<Rc<dyn Move<Column>>>, Option<Score>) {
-        let principal = hash_from_game(g.clone());
-        let (conductor, receiver) = Conductor::init_conductor_and_band(principal, moves_ahead, p, self.nworkers, String::from(STRDMP));
-        conductor.claim_public_interest(g);
-        let (column, score) = self.await_verdict(receiver);
+        ) -> (Option<Arc<dyn Move<Column>>>, Option<Score>) {
+        // the hashes of the brute force search stand for games that only ever grow,
+        // pops would undo that. Pop Out is searched as far ahead as asked, like by any strategy.
+        if g.read().unwrap().pop_out() {
+            return search_best_move(self, g, p, moves_ahead, false);
+        }
+        let rules = {
+            let game = g.read().unwrap();
+            let (width, height) = game.dimensions();
+            (width, height, game.win_length())
+        };
+        // a board too large for the hashes is no business of the brute force search
+        if rules.0 * rules.1 > MAX_CELLS {
+            return (None, None);
+        }
+        let solved = self.config.endgame.as_ref().and_then(|table| table.probe(&*g.read().unwrap(), p));
+        if let Some((column, score)) = solved {
+            return (Some(Arc::new(ConnectFourMove{ data:column })), Some(score));
+        }
+        let (principal, swapped) = hash_from_state(g.read().unwrap().state(), rules.1);
+        let mut known = match &self.store {
+            Some(path) => load_decided_games(Path::new(path), p, rules),
+            None => HashMap::new(),
+        };
+        let (column, score) = match known.get(&principal) {
+            // solved in an earlier run
+            Some(GameRecord{ state: GameState::Decided(score, column) }) => (column.clone(), score.clone()),
+            _ => {
+                let mut interests = HashMap::new();
+                if let Some(checkpoint) = self.resumable_checkpoint(principal, p, moves_ahead, rules) {
+                    for (hash, state) in checkpoint.records.into_iter() {
+                        known.insert(hash, GameRecord{ state });
+                    }
+                    interests = checkpoint.interests;
+                }
+                let (conductor, receiver) = Conductor::init_conductor_and_band(
+                    principal, moves_ahead, p, rules, self, known, interests);
+                conductor.claim_public_interest(g);
+                self.await_verdict(receiver)
+            },
+        };
         match column {
             None =>  (None, Some(score)),
-            Some(column) => (Some(Rc::new(ConnectFourMove{ data:column })), Some(score)),
+            // the verdict was found for the hashed game, which may be mirrored
+            Some(column) => (Some(Arc::new(ConnectFourMove{ data:swap(&column, swapped, rules.0) })), Some(score)),
+        }
+    }
+
+    // brute forces the game to its end until the budget is used up, the best verdict found by then.
+    // Pop Out is deepened step by step like by any strategy
+    fn find_best_move_in_time(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            budget: Duration,
+            game_evaluation: bool
+        ) -> (Option<Arc<dyn Move<Column>>>, Option<Score>) {
+        let moves_left = g.read().unwrap().moves_left();
+        let moves_left = match moves_left {
+            Some(moves_left) => moves_left as i32,
+            None => return search_best_move_in_time(self, g, p, budget, game_evaluation),
+        };
+        let deadline = Instant::now() + budget;
+        let cancellation = CancellationToken::new();
+        let timer = cancellation.clone();
+        // the caller's token cancels the search as well
+        let outer = self.cancellation.clone();
+        thread::spawn(move || {
+            while Instant::now() < deadline && !timer.is_cancelled() && !outer.as_ref().is_some_and(|c| c.is_cancelled()) {
+                thread::sleep(Duration::from_millis(10));
+            }
+            timer.cancel();
+        });
+        let budgeted = BruteForceStrategy {
+            config: self.config.clone(),
+            store: self.store.clone(),
+            checkpoint: self.checkpoint.clone(),
+            checkpoint_interval: self.checkpoint_interval,
+            memory_limit: self.memory_limit,
+            cancellation: Some(cancellation.clone()),
+            progress: self.progress.clone(),
+        };
+        let best = budgeted.find_best_move(g, p, moves_left, false);
+        // the timer is not needed any more
+        cancellation.cancel();
+        best
+    }
+
+    // every move is brute forced to the end of the game, the opponent's replies as well
+    fn analyze_position(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            moves_ahead: i32,
+            _game_evaluation: bool
+        ) -> Vec<(Column, Score)> {
+        let g = permissive(g);
+        let mut scores = Vec::new();
+        let options = g.read().unwrap().possible_moves(p);
+        // like the default analysis, only drops are scored
+        for mv in options.into_iter().filter(|mv| mv.kind() == MoveKind::Drop) {
+            let score = g.write().unwrap().make_move(p, Arc::clone(&mv));
+            let score = match score {
+                Ok(Score::Undecided(pv)) => {
+                    match self.find_best_move(g.clone(), p.opponent(), moves_ahead, false) {
+                        (_, Some(advscore)) => advscore.reverse(),
+                        (_, None) => Score::Undecided(pv),
+                    }
+                },
+                Ok(score) => score,
+                Err(_) => continue,
+            };
+            g.write().unwrap().withdraw_move(p, Arc::clone(&mv)).unwrap();
+            scores.push((mv.data().clone(), score));
         }
+        scores
+    }
+
+    fn analyze(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            moves_ahead: i32,
+            _game_evaluation: bool
+        ) -> Analysis<Column> {
+        let g = permissive(g);
+        let scores = self.analyze_position(Arc::clone(&g), p, moves_ahead, false);
+        let variation = principal_variation(self, g, p, moves_ahead, false, false);
+        Analysis { scores, variation }
     }
 }
+// approximate bytes a record takes in the game store, the hash map's own share included
+pub const RECORD_MEMORY: usize = 64;
+
 pub static STRDMP: &str  = "strdmp";
-pub static mut LIMIT: u128 = 0;
-pub static mut BASICALLY_OVER: usize = 30;
 
 impl BruteForceStrategy {
-    pub fn new(nworkers:usize) -> Self {
+    pub fn new(config:BruteForceConfig) -> Self {
         BruteForceStrategy {
-            nworkers: nworkers,
+            config,
+            store: None,
+            checkpoint: None,
+            checkpoint_interval: Duration::from_secs(600),
+            memory_limit: None,
+            cancellation: None,
+            progress: None,
+        }
+    }
+
+    // the checkpoint of an unfinished search for the game, if there is one.
+    // a search that looked fewer moves ahead left records that are not good enough
+    fn resumable_checkpoint(&self, principal:GameHash, p:&Player, moves_ahead:i32, rules:(usize,usize,usize))
+    -> Option<Checkpoint> {
+        let path = Path::new(self.checkpoint.as_ref()?);
+        match read_checkpoint(path) {
+            Ok(checkpoint) => {
+                if checkpoint.principal == principal && checkpoint.player == *p && checkpoint.rules == rules
+                    && checkpoint.moves_ahead == moves_ahead {
+                    return Some(checkpoint);
+                }
+                println!("checkpoint {} is for another game or depth, starting afresh", path.display());
+            },
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => println!("ignoring checkpoint {}: {}", path.display(), e),
         }
+        None
     }
 
     fn await_verdict(&self,
@@ -288,6 +543,7 @@ impl Conductor {
         game_store:Arc<Mutex<HashMap<GameHash,GameRecord>>>,
         principal:GameHash,
         p:&Player,
+        rules:(usize,usize,usize),
         printer:Box<&Path>,
     ) -> Result<(),std::io::Error> {
 
@@ -315,8 +571,8 @@ impl Conductor {
         println(format!("game store has {} records", (*gs).len()));
 
         // print the initial state
-        let g = Rc::new(RefCell::new(game_from_hash(principal)));
-        println(format!("{}", g.borrow().display()));
+        let g = Arc::new(RwLock::new(game_from_hash(principal, rules)));
+        println(format!("{}", g.read().unwrap().display()));
 
         if FULLSTOREDUMP {
             for (k, v) in (*gs).iter() {
@@ -328,7 +584,7 @@ impl Conductor {
         }
         
         // what are the options?
-        let options = g.borrow().possible_moves(p);
+        let options = g.read().unwrap().possible_moves(p);
         
         // leave if there ain't no possible moves left
         if options.is_empty() {
@@ -338,7 +594,7 @@ impl Conductor {
 
         // evaluate options
         for mv in options.into_iter() {
-            let score_result = g.borrow_mut().make_shading_move(p, Rc::clone(&mv));
+            let score_result = g.write().unwrap().make_shading_move(p, Arc::clone(&mv));
             match score_result {
                 Ok((score, state_after_mv)) => {
                     let hash_after_mv = hash_from_game(g.clone());
@@ -351,10 +607,10 @@ impl Conductor {
                         // found an undecided move, winning is still an option: let opponent make a move
                         Score::Undecided(_) => {
                             println(format!("{:<28}{:?}\t-> undecided from board", hash_after_mv, mv.data()));
-                            let anti_options = g.borrow().possible_moves(p.opponent());
+                            let anti_options = g.read().unwrap().possible_moves(p.opponent());
 
                             for anti_mv in anti_options.into_iter() {
-                                let anti_score_result = g.borrow_mut().make_shading_move(p.opponent(), Rc::clone(&anti_mv));
+                                let anti_score_result = g.write().unwrap().make_shading_move(p.opponent(), Arc::clone(&anti_mv));
                                 match anti_score_result {
                                     Ok((score, score_after_anti_mv)) => {
                                         let hash_after_anti_mv = hash_from_game(g.clone());
@@ -381,14 +637,14 @@ impl Conductor {
                                             },
                                         };
                                         println(format!("{:<28}\t{:?}\t-> {}", hash_after_anti_mv, anti_mv.data(), verdict));
-                                        g.borrow_mut().withdraw_move_unshading(p.opponent(), Rc::clone(&anti_mv), score_after_anti_mv);
+                                        g.write().unwrap().withdraw_move_unshading(p.opponent(), Arc::clone(&anti_mv), score_after_anti_mv);
                                     },
                                     Err(_) => panic!("unexpected error in anti move"),
                                 }
                             }
                         },
                     };
-                    g.borrow_mut().withdraw_move_unshading(p, Rc::clone(&mv), state_after_mv);
+                    g.write().unwrap().withdraw_move_unshading(p, Arc::clone(&mv), state_after_mv);
                 },
                 Err(_) => panic!("unexpected error in move"),
             }
@@ -396,33 +652,71 @@ impl Conductor {
         Ok(())
     }
 
-    fn init_conductor_and_band (principal:GameHash, moves_ahead:i32, p:&Player, nworkers:usize, dumpfile:String) -> (Self, Receiver<Verdict>) {
+    // known records and interests are taken over from earlier runs,
+    // the jobs of the records in state Novel or Recall are handed out right away
+    fn init_conductor_and_band (principal:GameHash, moves_ahead:i32, p:&Player, rules:(usize,usize,usize),
+            strategy:&BruteForceStrategy,
+            known:HashMap<GameHash,GameRecord>,
+            known_interests:HashMap<GameHash,Vec<GameHash>>) -> (Self, Receiver<Verdict>) {
         let (itx, interests) = channel::<Interest>();
         let interest_sender = itx.clone();
         let (final_verdict, rx) = channel::<Verdict>();
         
-        let game_store = Arc::new(Mutex::new(HashMap::<GameHash,GameRecord>::new()));
+        let game_store = Arc::new(Mutex::new(known));
         let player = p.clone();
         let principal:GameHash = principal;
+        let config = strategy.config.clone();
+        let nworkers = config.nworkers;
+        let store = strategy.store.clone();
+        let checkpoint = strategy.checkpoint.clone();
+        let checkpoint_interval = strategy.checkpoint_interval;
+        let record_limit = strategy.memory_limit.map(|bytes| bytes / RECORD_MEMORY);
+        let cancellation = strategy.cancellation.clone();
+        let progress = strategy.progress.clone();
 
 thread::spawn(move|| {
-    let mut interest_store:HashMap<GameHash,Vec<GameHash>> = HashMap::new();
+    let mut interest_store:HashMap<GameHash,Vec<GameHash>> = known_interests;
     let mut workers:Vec<Worker> = Vec::new();
     for i in 0..nworkers {
-        workers.push(Worker::spawn_worker(i, itx.clone(), moves_ahead, game_store.clone()));
+        workers.push(Worker::spawn_worker(i, itx.clone(), moves_ahead, rules, &config, cancellation.clone(), game_store.clone()));
     }
+    // the records of awaited games may have been evicted before a checkpoint was written
+    for hash in interest_store.keys() {
+        game_store.lock().unwrap().entry(*hash).or_insert(GameRecord{state: GameState::Novel,});
+    }
+    let queued:Vec<GameHash> = game_store.lock().unwrap().iter()
+        .filter(|(_, record)| matches!(record.state, GameState::Novel | GameState::Recall))
+        .map(|(hash, _)| *hash)
+        .collect();
+    for (i, hash) in queued.into_iter().enumerate() {
+        let worker = workers.get_mut(i % nworkers).unwrap();
+        if let Err(e) = worker.job_box.send((hash, player.clone())) {
+            println!("cannot submit queued job to {} ({}). worker has quit?", worker.id, e);
+        }
+        worker.pending_jobs += 1;
+    }
+    let mut last_checkpoint = Instant::now();
+    // the store is not searched for evictable records again before it has grown by a quarter
+    let mut eviction_threshold = record_limit;
 
-    //2: print out some interests
-    /*2*/let mut job_counter = 0;
-    //2:
+    let mut job_counter:u64 = 0;
     loop {
-        if let Ok(interest) = interests.recv() {
-            //2:
-            /*2*/job_counter += 1;
-            /*2*/if job_counter%1000000 == 0 {
-            /*2*/   println!("{}\t{}\t{}\t{:?}", interest_store.len(), game_store.lock().unwrap().len(), job_counter, interest.interesting);
-            /*2*/}
-            //2:
+        if cancellation.as_ref().is_some_and(|c| c.is_cancelled()) {
+            Conductor::dismiss_workers(&workers);
+            // keep what was found so far, a checkpoint lets the search go on later
+            if let Some(path) = &store {
+                if let Err(e) = save_decided_games(Path::new(path), &game_store, &player, rules) {
+                    println!("cannot save game store {}: {}", path, e);
+                }
+            }
+            if let Some(path) = &checkpoint {
+                Conductor::write_checkpoint(Path::new(path), &game_store, &interest_store, principal, &player, moves_ahead, rules);
+            }
+            final_verdict.send(Conductor::partial_verdict(&game_store, principal, &player, moves_ahead, rules)).unwrap();
+            break;
+        }
+        // wake up now and then to look for a cancellation
+        if let Ok(interest) = interests.recv_timeout(Duration::from_millis(100)) {
             match (interest.interesting, interest.interested, interest.record) {
                 // worker has finished job
                 (None, Some(_), None) => panic!("if job is done a verdict is expected"),
@@ -430,23 +724,55 @@ thread::spawn(move|| {
                     // note changed job pendencies
                     let worker_id = interest.worker_id.unwrap();
                     workers.get_mut(worker_id).unwrap().pending_jobs -= 1;
+                    job_counter += 1;
+                    if let (Some(report), Some(n)) = (&progress, config.progress_interval) {
+                        if n > 0 && job_counter.is_multiple_of(n) {
+                            report(&Progress {
+                                records: game_store.lock().unwrap().len(),
+                                interests: interest_store.len(),
+                                jobs: job_counter,
+                            });
+                        }
+                    }
 
                     // submit new jobs if this game is decided now
                     let finished = interested;
-                    {
+                    let decided_before = {
                         let mut gst = game_store.lock().unwrap();
                         match (*gst).get(&finished) {
-                            Some(x) => {
-                                match &x.state {
-                                    GameState::Decided(score, column) => panic!("unexpected state for {}: Decided({:?}, {:?})", &finished, score, column),
-                                    GameState::Undecided => panic!("unexpected state for {}: Undecided", &finished),
-                                    _ => (*gst).insert(finished, GameRecord{state: newstate.clone(),}),
+                            // the job was queued twice after an eviction, the first one has decided the game
+                            Some(GameRecord{ state: GameState::Decided(_,_) }) => true,
+                            // the record may have been evicted while the job was queued
+                            _ => {
+                                (*gst).insert(finished, GameRecord{state: newstate.clone(),});
+                                false
+                            },
+                        }
+                    };
+                    // a game that claimed an interest in a game decided meanwhile is interested in itself,
+                    // it is recalled right away if its job ends undecided
+                    if let (false, GameState::Undecided) = (decided_before, &newstate) {
+                        let recall = match interest_store.get_mut(&finished) {
+                            Some(record) if record.contains(&finished) => {
+                                record.retain(|h| *h != finished);
+                                if record.is_empty() {
+                                    interest_store.remove(&finished);
                                 }
+                                true
                             },
-                            None => panic!("game {} should have a record!", &finished),
+                            _ => false,
                         };
+                        if recall {
+                            game_store.lock().unwrap().insert(finished, GameRecord{state: GameState::Recall,});
+                            let wid = workers.iter().min_by_key(|w| w.pending_jobs).unwrap().id;
+                            let worker = workers.get_mut(wid).unwrap();
+                            if let Err(e) = worker.job_box.send((finished, player.clone())) {
+                                println!("cannot submit recalled job to {} ({}). worker has quit?", worker.id, e);
+                            }
+                            worker.pending_jobs += 1;
+                        }
                     }
-                    if let GameState::Decided(score,column) = newstate {
+                    if let (false, GameState::Decided(score,column)) = (decided_before, newstate) {
                         if let Some(parents) = interest_store.remove(&finished) {
                             for jobhash in parents.into_iter() {
 
@@ -483,19 +809,20 @@ thread::spawn(move|| {
                                 worker.pending_jobs += 1;
                             }
                         } else if finished == principal {
-                            for w in workers {
-                                loop {
-                                    match w.job_box.send((-1, Player::Black)) {
-                                        Ok(_) => break,
-                                        Err(_) => {
-                                            println!("anonymous worker?");
-                                            thread::sleep(Duration::from_millis(1));
-                                        },
-                                    }
+                            Conductor::dismiss_workers(&workers);
+                            if let Some(dumpfile) = &config.dump {
+                                let printer = Box::new(Path::new(&dumpfile[..]));
+                                Conductor::dump_store(game_store.clone(), principal, &player, rules, printer).unwrap();
+                            }
+                            if let Some(path) = &store {
+                                if let Err(e) = save_decided_games(Path::new(path), &game_store, &player, rules) {
+                                    println!("cannot save game store {}: {}", path, e);
                                 }
                             }
-                            let printer = Box::new(Path::new(&dumpfile[..]));
-                            Conductor::dump_store(game_store.clone(), principal, &player, printer).unwrap();
+                            // nothing left to resume
+                            if let Some(path) = &checkpoint {
+                                let _ = std::fs::remove_file(path);
+                            }
                             final_verdict.send(Verdict{
                                 score: score.clone(),
                                 column: column.clone(),
@@ -565,6 +892,18 @@ thread::spawn(move|| {
                     }
                 },
                 (None, None, _) => panic!("doesn't make sense"),
+            }
+            if let (Some(limit), Some(threshold)) = (record_limit, eviction_threshold) {
+                if game_store.lock().unwrap().len() > threshold {
+                    let left = Conductor::evict_records(&game_store, &interest_store, principal, &player, rules, limit);
+                    eviction_threshold = Some(cmp::max(limit, left + limit / 4));
+                }
+            }
+            if let Some(path) = &checkpoint {
+                if last_checkpoint.elapsed() >= checkpoint_interval {
+                    Conductor::write_checkpoint(Path::new(path), &game_store, &interest_store, principal, &player, moves_ahead, rules);
+                    last_checkpoint = Instant::now();
+                }
             }         
         }
     }
@@ -572,8 +911,108 @@ thread::spawn(move|| {
         (Conductor{sender:interest_sender,}, rx)
     }
 
+    fn dismiss_workers(workers:&[Worker]) {
+        for w in workers {
+            // a worker that has quit on a cancellation already cannot be told anymore
+            let _ = w.job_box.send((-1, Player::Black));
+        }
+    }
+
+    fn write_checkpoint(
+            path:&Path,
+            game_store:&Arc<Mutex<HashMap<GameHash,GameRecord>>>,
+            interest_store:&HashMap<GameHash,Vec<GameHash>>,
+            principal:GameHash,
+            p:&Player,
+            moves_ahead:i32,
+            rules:(usize,usize,usize)) {
+        // the worker of a locked game is gone once the checkpoint is resumed, its job is handed out again
+        let records = game_store.lock().unwrap().iter()
+            .map(|(hash, record)| match record.state {
+                GameState::Locked => (*hash, GameState::Novel),
+                _ => (*hash, record.state.clone()),
+            })
+            .collect();
+        if let Err(e) = write_checkpoint(path, &Checkpoint {
+            principal, player: p.clone(), moves_ahead, rules, records, interests: interest_store.clone(),
+        }) {
+            println!("cannot write checkpoint {}: {}", path.display(), e);
+        }
+    }
+
+    // the verdict of an unfinished search: decided if the games two moves ahead are known well enough,
+    // otherwise the move the heuristic prefers with an undecided score
+    fn partial_verdict(
+            game_store:&Arc<Mutex<HashMap<GameHash,GameRecord>>>,
+            principal:GameHash,
+            p:&Player,
+            moves_ahead:i32,
+            rules:(usize,usize,usize)) -> Verdict {
+        if let Some(GameRecord{ state: GameState::Decided(score, column) }) = game_store.lock().unwrap().get(&principal) {
+            return Verdict { score: score.clone(), column: column.clone() };
+        }
+        if let (GameState::Decided(score, column), _) = Worker::two_moves_ahead_inquiry(game_store, principal, p, 0, rules) {
+            return Verdict { score, column };
+        }
+        let game = Arc::new(RwLock::new(game_from_hash(principal, rules)));
+        match ConnectFourStrategy::default().find_best_move(game, p, moves_ahead, false) {
+            (Some(mv), Some(score)) => Verdict { score, column: Some(mv.data().clone()) },
+            _ => Verdict { score: Score::Undecided(0.5), column: None },
+        }
+    }
+
+    // shrinks the game store to three quarters of the limit and returns the number of records left.
+    // undecided records go first, then the ones with the fewest stones. the principal is kept and so
+    // are the decided records two moves ahead of the games waiting for a job or an interest, they are
+    // looked up once those are searched. evicted records are simply searched again if claimed later on.
+    fn evict_records(
+            game_store:&Arc<Mutex<HashMap<GameHash,GameRecord>>>,
+            interest_store:&HashMap<GameHash,Vec<GameHash>>,
+            principal:GameHash,
+            p:&Player,
+            rules:(usize,usize,usize),
+            limit:usize) -> usize {
+        // the records to evict first, as many as there are too many, keeping the needed ones
+        fn candidates(gs:&HashMap<GameHash,GameRecord>, principal:GameHash, needed:&HashSet<GameHash>, limit:usize)
+        -> Vec<(bool, u32, GameHash)> {
+            let mut candidates:Vec<(bool, u32, GameHash)> = gs.iter()
+                .filter(|(hash, _)| **hash != principal)
+                .map(|(hash, record)| (matches!(record.state, GameState::Decided(_,_)), stones_in_hash(*hash), *hash))
+                .filter(|(decided, _, hash)| !*decided || !needed.contains(hash))
+                .collect();
+            candidates.sort();
+            candidates.truncate(gs.len().saturating_sub(limit * 3 / 4));
+            candidates
+        }
+
+        // the games waiting are collected under the lock, the workers need not wait for their expansion
+        let waiting:Option<HashSet<GameHash>> = {
+            let gs = game_store.lock().unwrap();
+            if candidates(&gs, principal, &HashSet::new(), limit).iter().any(|(decided, _, _)| *decided) {
+                let mut waiting:HashSet<GameHash> = interest_store.values().flatten().cloned().collect();
+                for (hash, record) in (*gs).iter() {
+                    if let GameState::Novel | GameState::Recall = record.state {
+                        waiting.insert(*hash);
+                    }
+                }
+                Some(waiting)
+            } else {
+                None
+            }
+        };
+        let needed:HashSet<GameHash> = waiting.into_iter().flatten()
+            .flat_map(|hash| two_moves_ahead(hash, p, rules))
+            .collect();
+
+        let mut gs = game_store.lock().unwrap();
+        for (_, _, hash) in candidates(&gs, principal, &needed, limit) {
+            (*gs).remove(&hash);
+        }
+        (*gs).len()
+    }
+
     fn claim_public_interest(&self,
-            g: Rc<RefCell<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
         ) {
         let hash = hash_from_game(g);
         let sender = self.sender.clone();
@@ -598,8 +1037,9 @@ impl Worker {
         game_hash:GameHash,
         p:&Player,
         ahead_check:i32,
+        rules:(usize,usize,usize),
     ) -> (GameState,Vec<GameHash>) {
-        let mut cf = game_from_hash(game_hash);
+        let mut cf = game_from_hash(game_hash, rules);
         let cfs = ConnectFourStrategy::default();
 
         let options = cf.possible_moves(p);
@@ -614,13 +1054,13 @@ impl Worker {
 //1 println!("2mai\t{}", game_hash);
 //1:
         for mv in options.into_iter() {
-            let score = cf.make_shading_move(p, Rc::clone(&mv));
+            let score = cf.make_shading_move(p, Arc::clone(&mv));
             match score {
                 Ok((score,grayed_one)) => {
                     match score {
                         // found a winning move: immediate return
                         Score::Won(in_n) => {
-                            cf.withdraw_move_unshading(p, Rc::clone(&mv), grayed_one);
+                            cf.withdraw_move_unshading(p, Arc::clone(&mv), grayed_one);
                             return (GameState::Decided(Score::Won(in_n+1), Some(mv.data().clone())), vec![]);
                         },
                         // found an undecided move, winning is still an option: let opponent make a move
@@ -635,49 +1075,33 @@ impl Worker {
                                 let mut anti_won = false;
 
                                 for anti_mv in anti_options.into_iter() {
-                                    let anti_score = cf.make_shading_move(p.opponent(), Rc::clone(&anti_mv));
+                                    let anti_score = cf.make_shading_move(p.opponent(), Arc::clone(&anti_mv));
                                     match anti_score {
                                         Ok((score,grayed_two)) => {
                                             let mut check_twice = false;
                                             match score {
                                                 Score::Won(in_n) => { // opponent has a winning move: losing
                                                     doomed_moves.push((Score::Lost(in_n+2), mv.data().clone()));
-                                                    cf.withdraw_move_unshading(p.opponent(), Rc::clone(&anti_mv), grayed_two);
+                                                    cf.withdraw_move_unshading(p.opponent(), Arc::clone(&anti_mv), grayed_two);
                                                     anti_won = true;
                                                     break;
                                                 },
                                                 Score::Lost(in_n) => { anti_doomed_moves.push((Score::Lost(in_n+1), mv.data().clone())); },
                                                 Score::Remis(in_n) => { anti_draw_moves.push((Score::Remis(in_n+1), mv.data().clone())); },
                                                 Score::Undecided(_) => { // unclear from the bord: check game store
-                                                    let (hash, swapped) = hash_from_state(cf.state());
+                                                    let (hash, swapped) = hash_of(&cf);
                                                     let gs = game_store.lock().unwrap();
                                                     if let Some(record) = (*gs).get(&hash) {
-                                                        fn swap(column:&Column, swapped:bool)-> Column {
-                                                            if swapped {
-                                                                match column {
-                                                                    Column::One => Column::Seven,
-                                                                    Column::Two => Column::Six,
-                                                                    Column::Three => Column::Five,
-                                                                    Column::Four => Column::Four,
-                                                                    Column::Five => Column::Three,
-                                                                    Column::Six => Column::Two,
-                                                                    Column::Seven => Column::One,
-                                                                    Column::Zero => Column::Zero,
-                                                                }
-                                                            } else {
-                                                                column.clone()
-                                                            }
-                                                        }
                                                         match &record.state {
                                                             GameState::Decided(record_score,_) => match record_score {
                                                                 Score::Lost(in_n) => { // opponent can reach a lost game: losing
-                                                                    doomed_moves.push((Score::Lost(in_n+2), swap(mv.data(), swapped)));
-                                                                    cf.withdraw_move_unshading(p.opponent(), Rc::clone(&anti_mv), grayed_two);
+                                                                    doomed_moves.push((Score::Lost(in_n+2), swap(mv.data(), swapped, cf.width())));
+                                                                    cf.withdraw_move_unshading(p.opponent(), Arc::clone(&anti_mv), grayed_two);
                                                                     anti_won = true;
                                                                     break;
                                                                 },
-                                                                Score::Remis(in_n) => { anti_draw_moves.push((Score::Remis(in_n+1), swap(mv.data(), swapped))); },
-                                                                Score::Won(in_n) => { anti_doomed_moves.push((Score::Lost(in_n+1), swap(mv.data(), swapped))); },
+                                                                Score::Remis(in_n) => { anti_draw_moves.push((Score::Remis(in_n+1), swap(mv.data(), swapped, cf.width()))); },
+                                                                Score::Won(in_n) => { anti_doomed_moves.push((Score::Lost(in_n+1), swap(mv.data(), swapped, cf.width()))); },
                                                                 Score::Undecided(_) => { anti_open_moves.push((hash,swapped)); },
                                                             }
                                                             _ => { anti_open_moves.push((hash,swapped)); },
@@ -690,30 +1114,30 @@ impl Worker {
                                             if check_twice {
                                                 // hash has no record yet
                                                 // for saving memory filter Undecided by find_best_move(  ,2 steps ahead,  )
-                                                // TODO: cloning is inefficient. Rearrange the whole function and use Rc<RefCell<>> from the start.
+                                                // TODO: cloning is inefficient. Rearrange the whole function and use Arc<RwLock<>> from the start.
                                                 let cfc = cf.clone();
 //println!("uh-oh {}", cfc.display());
-                                                let cfr =  Rc::new(RefCell::new(cfc));
+                                                let cfr =  Arc::new(RwLock::new(cfc));
                                                 match cfs.find_best_move(cfr,p,ahead_check,false) {
                                                     (Some(mv), Some(score)) => {
 //println!("{:?} {:?}", mv.data(), score);
                                                         match score {
                                                         Score::Lost(in_n) => { // opponent can reach a lost game: losing
                                                             doomed_moves.push((Score::Lost(in_n+2), mv.data().clone()));
-                                                            cf.withdraw_move_unshading(p.opponent(), Rc::clone(&anti_mv), grayed_two);
+                                                            cf.withdraw_move_unshading(p.opponent(), Arc::clone(&anti_mv), grayed_two);
                                                             anti_won = true;
                                                             break;
                                                         },
                                                         Score::Remis(in_n) => { anti_draw_moves.push((Score::Remis(in_n+1), mv.data().clone())); },
                                                         Score::Won(in_n) => { anti_doomed_moves.push((Score::Lost(in_n+1), mv.data().clone())); },
-                                                        Score::Undecided(_) => { anti_open_moves.push(hash_from_state(cf.state())); },
+                                                        Score::Undecided(_) => { anti_open_moves.push(hash_of(&cf)); },
                                                     }},
                                                     (_,_) => {
                                                         panic!("no move!\n{}", cf.display());
                                                     },
                                                 };
                                             }
-                                            cf.withdraw_move_unshading(p.opponent(), Rc::clone(&anti_mv), grayed_two);
+                                            cf.withdraw_move_unshading(p.opponent(), Arc::clone(&anti_mv), grayed_two);
                                         },
                                         Err(_) => panic!("unexpected error in anti move"),
                                     }
@@ -733,7 +1157,7 @@ impl Worker {
                                 } else if !anti_doomed_moves.is_empty() { // opponent can only lose
                                     let (score, _) = anti_doomed_moves.first().unwrap();
                                     if let Score::Lost(in_n) = score {
-                                        cf.withdraw_move_unshading(p, Rc::clone(&mv), grayed_one);
+                                        cf.withdraw_move_unshading(p, Arc::clone(&mv), grayed_one);
                                         return (GameState::Decided(Score::Won(in_n+1), Some(mv.data().clone())), vec![]);
                                     }
                                 }
@@ -743,7 +1167,7 @@ impl Worker {
                         Score::Remis(in_n) => { draw_moves.push((Score::Remis(in_n+1), mv.data().clone())); },
                         Score::Lost(in_n) => { doomed_moves.push((Score::Lost(in_n+1), mv.data().clone())); },
                     };
-                    cf.withdraw_move_unshading(p, Rc::clone(&mv), grayed_one);
+                    cf.withdraw_move_unshading(p, Arc::clone(&mv), grayed_one);
                 },
                 Err(_) => panic!("unexpected error in move"),
             }
@@ -770,23 +1194,23 @@ impl Worker {
 
     fn game_simulation(
         moves_ahead:i32,
-        g:Rc<RefCell<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+        time_limit:Duration,
+        g:Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
         p:&Player
     ) -> GameState {
         let cfs = ConnectFourStrategy::default();
 // debug
-//println!("{}\n{}\n{}", g.borrow().display(), moves_ahead, p);
+//println!("{}\n{}\n{}", g.read().unwrap().display(), moves_ahead, p);
 //
         let mut depth = moves_ahead;
         let mut then = Instant::now();
         loop {
             match cfs.find_best_move(g.clone(),p,depth,false) {
                 (Some(mv), Some(score)) => match score {
-                    Score::Undecided(_) => unsafe {
-                        let now = Instant::now();
-                        let took = now.duration_since(then).as_millis();
-//println!("{} {}", LIMIT, took);
-                        if took >= LIMIT { return GameState::Undecided; }
+                    Score::Undecided(_) => {
+                        let took = then.elapsed();
+//println!("{:?} {:?}", time_limit, took);
+                        if took >= time_limit { return GameState::Undecided; }
                         else {
                             depth += 1;
 //println!("{}", depth);
@@ -797,7 +1221,7 @@ impl Worker {
                 },
                 (_,_) => {
 //3:
-/*3*/ println!("no move!\n{}", g.borrow().display());
+/*3*/ println!("no move!\n{}", g.read().unwrap().display());
 //3:
                     return GameState::Undecided;
                 },
@@ -832,29 +1256,42 @@ impl Worker {
     fn do_the_job(
             game_store:&Arc<Mutex<HashMap<GameHash,GameRecord>>>,
             moves_ahead:i32,
+            rules:(usize,usize,usize),
+            config:&BruteForceConfig,
             interest:&Sender<Interest>,
             hash:GameHash,
             p:&Player) -> Result<GameState,String> {
+        // a job queued twice after an eviction may find its game decided already
+        if let Some(GameRecord{ state: GameState::Decided(score, column) }) = game_store.lock().unwrap().get(&hash) {
+            return Ok(GameState::Decided(score.clone(), column.clone()));
+        }
         match Worker::lock_hash(&game_store, hash) {
             // 0. quit job if game is locked or decided
             Err(message) => {
                 return Err(message);
             },
             Ok(new) => { // new game, never simulated
-                let game = Rc::new(RefCell::new(game_from_hash(hash)));
+                let game = Arc::new(RwLock::new(game_from_hash(hash, rules)));
+
+                // 0. the endgame table knows the game, it is shaded the same way
+                if let Some(table) = &config.endgame {
+                    let solved = table.lookup_game(&game.read().unwrap(), p);
+                    if let Some((column, score)) = solved {
+                        return Ok(GameState::Decided(score, Some(column)));
+                    }
+                }
 
                 if new { // new game, never simulated
             // 1. try to find a solution from game simulation - if not already tried!
                     // once 30 or so stones were dropped, run simulation till the bitter end
-                    let moah = match game.clone().borrow().dropped_stones() {
-                        n => unsafe {
-                            if n >= BASICALLY_OVER {
-                                (ConnectFour::width() * ConnectFour::height() - BASICALLY_OVER) as i32
-                            } else { moves_ahead }
-                        }
+                    let moah = match game.clone().read().unwrap().dropped_stones() {
+                        n if n >= config.basically_over => {
+                            (rules.0 * rules.1).saturating_sub(config.basically_over) as i32
+                        },
+                        _ => moves_ahead,
                     };
 
-                    match Worker::game_simulation(moah, game.clone(), p) {
+                    match Worker::game_simulation(moah, config.time_limit, game.clone(), p) {
                         GameState::Decided(verdict, mv) => { 
                             return Ok(GameState::Decided(verdict, mv));
                         },
@@ -866,7 +1303,8 @@ impl Worker {
             // 2. try to find a solution from the game store two moves ahead
                 match Worker::two_moves_ahead_inquiry(
                     &game_store, hash, p,
-                    if moves_ahead < 2 {0} else {moves_ahead-2}
+                    if moves_ahead < 2 {0} else {moves_ahead-2},
+                    rules
                 ) {
                     (GameState::Decided(verdict, mv),_) => {
                         return Ok(GameState::Decided(verdict, mv));
@@ -894,7 +1332,8 @@ impl Worker {
                 GameState::Novel => {
                     return Ok(true);
                 },
-                GameState::Recall => {
+                // a job queued twice after an eviction, the game was searched already
+                GameState::Recall | GameState::Undecided => {
                     return Ok(false);
                 }
                 gamestate => {
@@ -902,7 +1341,8 @@ impl Worker {
                 }
             }
         } else {
-           return Err(String::from("there should be a record"));
+            // evicted records are searched again
+            return Ok(true);
         }
     }
 
@@ -910,9 +1350,12 @@ impl Worker {
             wid:usize,
             interest:Sender<Interest>,
             moves_ahead:i32,
+            rules:(usize,usize,usize),
+            config:&BruteForceConfig,
+            cancellation:Option<CancellationToken>,
             game_store:Arc<Mutex<HashMap<GameHash,GameRecord>>>) -> Worker {
         let (tx,jobs) = channel::<(GameHash,Player)>();
-        let moves_ahead = moves_ahead;
+        let config = config.clone();
 // debug
 //println!("hello {}", wid);
 //
@@ -927,11 +1370,13 @@ thread::spawn(move|| {
 //
                 break;
             },
+            // the jobs still queued are dropped
+            Ok(_) if cancellation.as_ref().is_some_and(|c| c.is_cancelled()) => break,
             Ok((hash,p)) => {
 // debug
 //println!("job for {}: {}", wid, hash);
 //
-                if let Ok(verdict) = Worker::do_the_job(&game_store, moves_ahead, &interest, hash, &p) {
+                if let Ok(verdict) = Worker::do_the_job(&game_store, moves_ahead, rules, &config, &interest, hash, &p) {
                     match interest.send(Interest{
                         interested: Some(hash), interesting: None, worker_id: Some(wid), record: Some(verdict),
                     }) {
@@ -961,3 +1406,290 @@ panic!("job finished badly");
         }
     }
 }
+
+//### persistent store ############################################################################
+
+// store and checkpoint files start with their magic bytes, the format version and the board
+// rules: width, height and win length. a decision takes three bytes: kind of score, in_n and column.
+const STORE_MAGIC: &[u8; 4] = b"CFGS";
+const STORE_VERSION: u32 = 2;
+const CHECKPOINT_MAGIC: &[u8; 4] = b"CFCP";
+const CHECKPOINT_VERSION: u32 = 2;
+const HEADER_LEN: usize = 11;
+const NO_COLUMN: u8 = 0xff;
+
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+fn write_header(bytes:&mut Vec<u8>, magic:&[u8; 4], version:u32, (width, height, win_length):(usize,usize,usize)) {
+    bytes.extend_from_slice(magic);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.push(width as u8);
+    bytes.push(height as u8);
+    bytes.push(win_length as u8);
+}
+
+// the board rules the file is for
+fn check_header(bytes:&[u8], magic:&[u8; 4], version:u32, path:&Path) -> Result<(usize,usize,usize), std::io::Error> {
+    if bytes.len() < HEADER_LEN || &bytes[0..4] != magic {
+        return Err(invalid_data(format!("{} has an unknown format", path.display())));
+    }
+    let found = read_u32(&bytes[4..8]);
+    if found != version {
+        return Err(invalid_data(format!("{} has format version {}, expected {}", path.display(), found, version)));
+    }
+    Ok((bytes[8] as usize, bytes[9] as usize, bytes[10] as usize))
+}
+
+fn read_u32(bytes:&[u8]) -> u32 {
+    let mut b = [0u8; 4];
+    b.copy_from_slice(&bytes[0..4]);
+    u32::from_le_bytes(b)
+}
+
+fn read_hash(bytes:&[u8]) -> GameHash {
+    let mut b = [0u8; 16];
+    b.copy_from_slice(&bytes[0..16]);
+    i128::from_le_bytes(b)
+}
+
+fn encode_player(p:&Player) -> Result<u8, std::io::Error> {
+    match p {
+        Player::Black => Ok(0),
+        Player::White => Ok(1),
+        Player::Gray => Err(invalid_data(String::from("gray never moves"))),
+    }
+}
+
+fn decode_player(b:u8) -> Result<Player, std::io::Error> {
+    match b {
+        0 => Ok(Player::Black),
+        1 => Ok(Player::White),
+        b => Err(invalid_data(format!("unknown player {}", b))),
+    }
+}
+
+fn encode_decision(score:&Score, column:&Option<Column>) -> Result<[u8; 3], std::io::Error> {
+    let (kind, in_n) = match score {
+        Score::Won(in_n) => (0, in_n),
+        Score::Lost(in_n) => (1, in_n),
+        Score::Remis(in_n) => (2, in_n),
+        Score::Undecided(_) => return Err(invalid_data(String::from("undecided score"))),
+    };
+    let column = match column {
+        Some(column) => column.to_usize() as u8,
+        None => NO_COLUMN,
+    };
+    Ok([kind, *in_n as u8, column])
+}
+
+fn decode_decision(bytes:&[u8]) -> Result<(Score, Option<Column>), std::io::Error> {
+    let in_n = bytes[1] as u32;
+    let score = match bytes[0] {
+        0 => Score::Won(in_n),
+        1 => Score::Lost(in_n),
+        2 => Score::Remis(in_n),
+        b => return Err(invalid_data(format!("unknown score {}", b))),
+    };
+    let column = match bytes[2] {
+        NO_COLUMN => None,
+        c => match Column::from_usize(c as usize) {
+            Ok(column) => Some(column),
+            Err(_) => return Err(invalid_data(format!("unknown column {}", c))),
+        },
+    };
+    Ok((score, column))
+}
+
+// store records: hash (i128, little endian), player to move and decision
+const STORE_RECORD_LEN: usize = 20;
+
+// a decided game as it is kept in the store file, the column refers to the hashed game
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredGame {
+    pub hash: GameHash,
+    pub player: Player,
+    pub score: Score,
+    pub column: Option<Column>,
+}
+
+// the games of a store file for a board of the rules
+pub fn read_store(path:&Path, rules:(usize,usize,usize)) -> Result<Vec<StoredGame>, std::io::Error> {
+    let bytes = std::fs::read(path)?;
+    let (width, height, win_length) = check_header(&bytes, STORE_MAGIC, STORE_VERSION, path)?;
+    if (width, height, win_length) != rules {
+        return Err(invalid_data(format!("{} is for connect {} on a {}x{} board", path.display(), win_length, width, height)));
+    }
+    if !(bytes.len() - HEADER_LEN).is_multiple_of(STORE_RECORD_LEN) {
+        return Err(invalid_data(format!("{} is truncated", path.display())));
+    }
+
+    let mut games = Vec::with_capacity((bytes.len() - HEADER_LEN) / STORE_RECORD_LEN);
+    for record in bytes[HEADER_LEN..].chunks(STORE_RECORD_LEN) {
+        let (score, column) = decode_decision(&record[17..20])?;
+        games.push(StoredGame { hash: read_hash(record), player: decode_player(record[16])?, score, column });
+    }
+    Ok(games)
+}
+
+pub fn write_store(path:&Path, rules:(usize,usize,usize), games:&[StoredGame]) -> Result<(), std::io::Error> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + games.len() * STORE_RECORD_LEN);
+    write_header(&mut bytes, STORE_MAGIC, STORE_VERSION, rules);
+    for game in games {
+        bytes.extend_from_slice(&game.hash.to_le_bytes());
+        bytes.push(encode_player(&game.player)?);
+        bytes.extend_from_slice(&encode_decision(&game.score, &game.column)?);
+    }
+    write_atomically(path, &bytes)
+}
+
+// writes to a temporary file first, so no other process ever reads a half written file
+fn write_atomically(path:&Path, bytes:&[u8]) -> Result<(), std::io::Error> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    std::fs::write(&temporary, bytes)?;
+    std::fs::rename(&temporary, path)
+}
+
+// the decided games of the player from the store file.
+// an unreadable store is reported and ignored, it is replaced once the next verdict is found.
+fn load_decided_games(path:&Path, p:&Player, rules:(usize,usize,usize)) -> HashMap<GameHash,GameRecord> {
+    let mut records = HashMap::new();
+    match read_store(path, rules) {
+        Ok(games) => for game in games.into_iter().filter(|game| game.player == *p) {
+            if let Err(e) = PositionKey::from_hash(game.hash, rules) {
+                println!("ignoring a game of store {}: {}", path.display(), e);
+                continue;
+            }
+            records.insert(game.hash, GameRecord{ state: GameState::Decided(game.score, game.column) });
+        },
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => (),
+        Err(e) => println!("ignoring game store {}: {}", path.display(), e),
+    }
+    records
+}
+
+// adds the decided games of the player to the store file, keeping what other runs found
+fn save_decided_games(
+        path:&Path,
+        game_store:&Arc<Mutex<HashMap<GameHash,GameRecord>>>,
+        p:&Player,
+        rules:(usize,usize,usize)) -> Result<(), std::io::Error> {
+    let mut games = read_store(path, rules).unwrap_or_default();
+    let gs = game_store.lock().unwrap();
+    games.retain(|game| {
+        game.player != *p || !matches!((*gs).get(&game.hash), Some(GameRecord{ state: GameState::Decided(_,_) }))
+    });
+    for (hash, record) in (*gs).iter() {
+        if let GameState::Decided(score, column) = &record.state {
+            games.push(StoredGame { hash: *hash, player: p.clone(), score: score.clone(), column: column.clone() });
+        }
+    }
+    write_store(path, rules, &games)
+}
+
+//### checkpoints #################################################################################
+
+// the complete state of a conductor: every record, the interest graph and the principal game.
+// the queued jobs are the records in state Novel or Recall, they are handed out again on resume.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Checkpoint {
+    pub principal: GameHash,
+    pub player: Player,
+    pub moves_ahead: i32,
+    // width, height and win length of the board, they are kept in the header
+    pub rules: (usize,usize,usize),
+    pub records: HashMap<GameHash,GameState>,
+    // the interested games for each interesting game
+    pub interests: HashMap<GameHash,Vec<GameHash>>,
+}
+
+// after the header: principal (i128), player, moves_ahead (i32), number of records (u32),
+// the records with hash, state and decision, number of interests (u32) and the interests
+// with hash, number of interested games (u32) and their hashes
+const CHECKPOINT_RECORD_LEN: usize = 20;
+
+fn encode_state(state:&GameState) -> Result<[u8; 4], std::io::Error> {
+    Ok(match state {
+        GameState::Decided(score, column) => {
+            let decision = encode_decision(score, column)?;
+            [0, decision[0], decision[1], decision[2]]
+        },
+        GameState::Undecided => [1, 0, 0, 0],
+        GameState::Novel => [2, 0, 0, 0],
+        GameState::Recall => [3, 0, 0, 0],
+        GameState::Locked => [4, 0, 0, 0],
+    })
+}
+
+fn decode_state(bytes:&[u8]) -> Result<GameState, std::io::Error> {
+    match bytes[0] {
+        0 => {
+            let (score, column) = decode_decision(&bytes[1..4])?;
+            Ok(GameState::Decided(score, column))
+        },
+        1 => Ok(GameState::Undecided),
+        2 => Ok(GameState::Novel),
+        3 => Ok(GameState::Recall),
+        4 => Ok(GameState::Locked),
+        b => Err(invalid_data(format!("unknown game state {}", b))),
+    }
+}
+
+pub fn write_checkpoint(path:&Path, checkpoint:&Checkpoint) -> Result<(), std::io::Error> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + 29 + checkpoint.records.len() * CHECKPOINT_RECORD_LEN);
+    write_header(&mut bytes, CHECKPOINT_MAGIC, CHECKPOINT_VERSION, checkpoint.rules);
+    bytes.extend_from_slice(&checkpoint.principal.to_le_bytes());
+    bytes.push(encode_player(&checkpoint.player)?);
+    bytes.extend_from_slice(&checkpoint.moves_ahead.to_le_bytes());
+    bytes.extend_from_slice(&(checkpoint.records.len() as u32).to_le_bytes());
+    for (hash, state) in checkpoint.records.iter() {
+        bytes.extend_from_slice(&hash.to_le_bytes());
+        bytes.extend_from_slice(&encode_state(state)?);
+    }
+    bytes.extend_from_slice(&(checkpoint.interests.len() as u32).to_le_bytes());
+    for (hash, interested) in checkpoint.interests.iter() {
+        bytes.extend_from_slice(&hash.to_le_bytes());
+        bytes.extend_from_slice(&(interested.len() as u32).to_le_bytes());
+        for h in interested.iter() {
+            bytes.extend_from_slice(&h.to_le_bytes());
+        }
+    }
+    write_atomically(path, &bytes)
+}
+
+pub fn read_checkpoint(path:&Path) -> Result<Checkpoint, std::io::Error> {
+    let bytes = std::fs::read(path)?;
+    let rules = check_header(&bytes, CHECKPOINT_MAGIC, CHECKPOINT_VERSION, path)?;
+    let truncated = || { invalid_data(format!("{} is truncated", path.display())) };
+    // hands out the next n bytes
+    let mut at = HEADER_LEN;
+    let mut next = |n:usize| -> Result<&[u8], std::io::Error> {
+        if at + n > bytes.len() { return Err(truncated()); }
+        at += n;
+        Ok(&bytes[at-n..at])
+    };
+
+    let principal = read_hash(next(16)?);
+    let player = decode_player(next(1)?[0])?;
+    let moves_ahead = read_u32(next(4)?) as i32;
+    let mut records = HashMap::new();
+    for _ in 0..read_u32(next(4)?) {
+        let record = next(CHECKPOINT_RECORD_LEN)?;
+        records.insert(read_hash(record), decode_state(&record[16..20])?);
+    }
+    let mut interests = HashMap::new();
+    for _ in 0..read_u32(next(4)?) {
+        let hash = read_hash(next(16)?);
+        let mut interested = Vec::new();
+        for _ in 0..read_u32(next(4)?) {
+            interested.push(read_hash(next(16)?));
+        }
+        interests.insert(hash, interested);
+    }
+    if next(1).is_ok() {
+        return Err(invalid_data(format!("{} has trailing bytes", path.display())));
+    }
+    Ok(Checkpoint { principal, player, moves_ahead, rules, records, interests })
+}
@@ -1,51 +1,158 @@
 //pub mod generic;
-use generic::{Game,Move,Player,Score,Strategy,Withdraw};
-use std::rc::Rc;
-use std::cell::RefCell;
-use std::cmp::{min};
+use generic::{Game,GameError,Move,MoveKind,Player,Score,Strategy,permissive};
+use bruteforce::MAX_CELLS;
+use book::{OpeningBook,PositionTable};
+use endgame::EndgameTable;
+use std::sync::{Arc,RwLock};
+use std::cmp::{min,max};
 
 //#################################################################################################
 // specifically Connect Four
 //#################################################################################################
 
 pub struct ConnectFour {
+    // a vector per column, the stones from the bottom up
     field: Vec<Vec<Option<Player>>>,
+    height: usize,
+    win_length: usize,
+    // the player whose turn it is, nobody's before the first move
+    next: Option<Player>,
+    status: GameStatus,
+    // a permissive game takes any stone at any time, for setting up and analysing positions
+    permissive: bool,
+    // Pop Out: instead of dropping a stone, a player may pop one of their own out of the bottom row
+    pop_out: bool,
+    // the moves that led to the field, the first one first
+    history: Vec<(Player, MoveKind, Column)>,
+    // the status before each move of the history, restored when the move is taken back
+    statuses: Vec<GameStatus>,
+    // the hash of the field, kept up to date move by move. None on boards too large for a hash
+    keys: Option<Keys>,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Hash)]
-pub enum Column {
-    One, Two, Three, Four, Five, Six, Seven, Zero
+// the hash bruteforce::hash_from_state takes, without going through the whole field.
+// like in Zobrist hashing every stone in every cell has a key that comes and goes with the stone,
+// but the keys are digits of base 4 added up rather than random numbers xored,
+// so a hash can still be turned back into its game.
+#[derive(Clone)]
+struct Keys {
+    width: usize,
+    height: usize,
+    // the field as it is and seen from the other side
+    direct: i128,
+    mirrored: i128,
+    // the halves of the field bias compares: the left columns and the right ones mirrored,
+    // the outer columns in the higher digits, stones ranked as bias ranks them and empty cells last
+    left: u128,
+    right: u128,
 }
 
+impl Keys {
+    fn new(width: usize, height: usize) -> Option<Keys> {
+        if width * height > MAX_CELLS {
+            return None;
+        }
+        let empty = 4u128.pow((width / 2 * height) as u32) - 1;
+        Some(Keys { width, height, direct: 0, mirrored: 0, left: empty, right: empty })
+    }
+
+    // adds the key of a stone in a cell or takes it out again
+    fn toggle(&mut self, n: usize, m: usize, stone: &Player, add: bool) {
+        let (w, h, half) = (self.width, self.height, self.width / 2);
+        let digit: i128 = match stone {
+            Player::White => 1,
+            Player::Black => 2,
+            Player::Gray => 3,
+        };
+        let direct = digit * 4i128.pow((n * h + m) as u32);
+        let mirrored = digit * 4i128.pow(((w - 1 - n) * h + m) as u32);
+        let rank: u128 = match stone {
+            Player::Black => 0,
+            Player::White => 1,
+            Player::Gray => 2,
+        };
+        // how much lower than an empty cell the stone makes its half
+        let below_empty = |i: usize| (3 - rank) * 4u128.pow((half * h - 1 - (i * h + m)) as u32);
+        if add {
+            self.direct += direct;
+            self.mirrored += mirrored;
+            if n < half {
+                self.left -= below_empty(n);
+            } else if n >= w - half {
+                self.right -= below_empty(w - 1 - n);
+            }
+        } else {
+            self.direct -= direct;
+            self.mirrored -= mirrored;
+            if n < half {
+                self.left += below_empty(n);
+            } else if n >= w - half {
+                self.right += below_empty(w - 1 - n);
+            }
+        }
+    }
+
+    // mirrored if bias would say so
+    fn hash(&self) -> (i128, bool) {
+        if self.right > self.left { (self.mirrored, true) } else { (self.direct, false) }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum GameStatus {
+    InProgress,
+    Won(Player),
+    Draw,
+}
+
+// the standard board
+pub const STANDARD_WIDTH: usize = 7;
+pub const STANDARD_HEIGHT: usize = 6;
+pub const STANDARD_WIN_LENGTH: usize = 4;
+
+const COLUMN_NAMES: [&str; 16] = [
+    "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight",
+    "Nine", "Ten", "Eleven", "Twelve", "Thirteen", "Fourteen", "Fifteen", "Sixteen",
+];
+// the widest board there are columns for
+pub const MAX_WIDTH: usize = 16;
+
+// a column counted from the left, whether a board is that wide is up to the board
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct Column(usize);
+
+#[allow(non_upper_case_globals)]
 impl Column {
+    pub const One: Column = Column(0);
+    pub const Two: Column = Column(1);
+    pub const Three: Column = Column(2);
+    pub const Four: Column = Column(3);
+    pub const Five: Column = Column(4);
+    pub const Six: Column = Column(5);
+    pub const Seven: Column = Column(6);
+    pub const Eight: Column = Column(7);
+    pub const Nine: Column = Column(8);
+
     pub fn to_usize(&self) -> usize {
-        match &self {
-            Column::One => 0x0,
-            Column::Two => 0x1,
-            Column::Three => 0x2,
-            Column::Four => 0x3,
-            Column::Five => 0x4,
-            Column::Six => 0x5,
-            Column::Seven => 0x6,
-            // Zero is for making the reverse function from_usize easier to use
-            Column::Zero => 0x99,
-        }
-    }
-
-    pub fn from_usize(i: usize) -> Self {
-        match i {
-            0x0 => Column::One,
-            0x1 => Column::Two,
-            0x2 => Column::Three,
-            0x3 => Column::Four,
-            0x4 => Column::Five,
-            0x5 => Column::Six,
-            0x6 => Column::Seven,
-            _ => Column::Zero,
+        self.0
+    }
+
+    pub fn from_usize(i: usize) -> Result<Self, GameError> {
+        if i < MAX_WIDTH {
+            Ok(Column(i))
+        } else {
+            Err(GameError::InvalidColumn(i))
         }
     }
 }
 
+// columns are written the way they used to be named, One to Sixteen
+impl std::fmt::Debug for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", COLUMN_NAMES[self.0])
+    }
+}
+
 pub struct ConnectFourMove {
     pub data: Column,
 }
@@ -61,43 +168,133 @@ impl Move<Column> for ConnectFourMove {
     }
 }
 
+// popping the bottom stone of a column, the stones above fall down by one cell
+pub struct PopOutMove {
+    pub data: Column,
+}
+
+impl Move<Column> for PopOutMove {
+    fn data(&self) -> &Column {
+        &self.data
+    }
+
+    fn display(&self) -> String {
+        format!("pop {:?}", self.data())
+    }
+
+    fn kind(&self) -> MoveKind {
+        MoveKind::Pop
+    }
+}
+
 impl Game<Column,Vec<Vec<Option<Player>>>> for ConnectFour {
-    fn possible_moves(&self, _: &Player) -> Vec<Rc<dyn Move<Column>>> {
-        let mut allowed: Vec<Rc<dyn Move<Column>>> = Vec::new();
+    fn possible_moves(&self, p: &Player) -> Vec<Arc<dyn Move<Column>>> {
+        let mut allowed: Vec<Arc<dyn Move<Column>>> = Vec::new();
         let mut i:usize = 0;
         for col in &self.field {
-            if col.len() < ConnectFour::height() {
-                allowed.push(Rc::new(ConnectFourMove {
-                    data: Column::from_usize(i)
+            if col.len() < self.height {
+                allowed.push(Arc::new(ConnectFourMove {
+                    data: Column::from_usize(i).unwrap()
                 }));
             }
             i += 1;
         }
+        // the pops after the drops
+        if self.pop_out && *p != Player::Gray {
+            for (i, col) in self.field.iter().enumerate() {
+                if col.first() == Some(&Some(p.clone())) {
+                    allowed.push(Arc::new(PopOutMove {
+                        data: Column::from_usize(i).unwrap()
+                    }));
+                }
+            }
+        }
         allowed
     }
 
-    fn make_move(&mut self, p: &Player, mv: Rc<dyn Move<Column>>) -> Result<Score, Withdraw> {
-        let n = mv.data().to_usize();
+    fn make_move(&mut self, p: &Player, mv: Arc<dyn Move<Column>>) -> Result<Score, GameError> {
+        if !self.permissive {
+            if self.status != GameStatus::InProgress {
+                return Err(GameError::GameOver);
+            }
+            if self.next.as_ref().is_some_and(|next| next != p) {
+                return Err(GameError::WrongTurn);
+            }
+        }
+        let n = self.column_index(mv.data())?;
         let m = self.field[n].len();
-        if ConnectFour::height() == m {
+        let score = if mv.kind() == MoveKind::Pop {
+            self.pop(p, n)
+        } else if self.height == m {
             // column is obviously already filled to the top
-            Err(Withdraw::NotAllowed)
+            Err(GameError::ColumnFull)
         } else {
             // drop the stone
             self.field[n].push(Some(p.clone()));
-            
-            
+            self.key_stone(n, m, p, true);
             // return the score
             self.get_score(p, n, m)
+        };
+        if score.is_ok() {
+            self.history.push((p.clone(), mv.kind(), mv.data().clone()));
+            self.statuses.push(self.status.clone());
+        }
+        // gray stones are nobody's, they neither win nor take a turn
+        if score.is_ok() && *p != Player::Gray {
+            match score {
+                Ok(Score::Won(_)) => { self.status = GameStatus::Won(p.clone()); },
+                Ok(Score::Lost(_)) => { self.status = GameStatus::Won(p.opponent().clone()); },
+                Ok(Score::Remis(_)) => { self.status = GameStatus::Draw; },
+                _ => (),
+            }
+            self.next = Some(p.opponent().clone());
         }
+        score
     }
 
-    fn withdraw_move(&mut self, _p: &Player, mv: Rc<dyn Move<Column>>) {
-        let n = mv.data().to_usize();
-        // un-drop the stone
-        if let None = self.field[n].pop() {
-            panic!("there should be a stone at column {:?}", mv.data());
+    fn withdraw_move(&mut self, p: &Player, mv: Arc<dyn Move<Column>>) -> Result<(), GameError> {
+        let n = self.column_index(mv.data())?;
+        // in a game played by the rules, only the last move is taken back
+        if !self.permissive && self.history.last() != Some(&(p.clone(), mv.kind(), mv.data().clone())) {
+            return Err(GameError::NotLastMove);
         }
+        if mv.kind() == MoveKind::Pop {
+            // push the popped stone back in from below
+            if self.field[n].len() == self.height {
+                return Err(GameError::ColumnFull);
+            }
+            self.key_column(n, false);
+            self.field[n].insert(0, Some(p.clone()));
+            self.key_column(n, true);
+        } else {
+            // un-drop the stone
+            match self.field[n].pop() {
+                Some(Some(stone)) => { self.key_stone(n, self.field[n].len(), &stone, false); },
+                Some(None) => (),
+                None => return Err(GameError::EmptyColumn),
+            }
+        }
+        // usually the last move is taken back, but not necessarily
+        let kind = mv.kind();
+        let mut restored = false;
+        if let Some(i) = self.history.iter().rposition(|(q, k, c)| q == p && *k == kind && c == mv.data()) {
+            self.history.remove(i);
+            let status = self.statuses.remove(i);
+            // the field is the one before the last move again
+            if i == self.history.len() {
+                self.status = status;
+                restored = true;
+            }
+        }
+        if *p != Player::Gray {
+            self.next = Some(p.clone());
+        }
+        if !restored && self.status != GameStatus::InProgress {
+            // a strict game was going on until the very last stone,
+            // a permissive one may have been decided long before
+            self.status = if self.permissive { self.scan_status() } else { GameStatus::InProgress };
+        }
+        Ok(())
     }
 
     fn display(&self) -> String {
@@ -121,6 +318,27 @@ impl Game<Column,Vec<Vec<Option<Player>>>> for ConnectFour {
     fn state(&self) -> &Vec<Vec<Option<Player>>> {
         &self.field
     }
+    fn replicate(&self) -> Option<Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>> {
+        let mut copy = self.clone();
+        copy.permissive = true;
+        Some(Arc::new(RwLock::new(copy)))
+    }
+    fn is_permissive(&self) -> bool {
+        self.permissive
+    }
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width(), self.height)
+    }
+    fn win_length(&self) -> usize {
+        self.win_length
+    }
+    fn pop_out(&self) -> bool {
+        self.pop_out
+    }
+    // every drop fills a cell, pops may go on for ever
+    fn moves_left(&self) -> Option<usize> {
+        if self.pop_out { None } else { Some(self.width() * self.height - self.dropped_stones()) }
+    }
 }
 
 enum Step {
@@ -132,20 +350,145 @@ enum Step {
 //### connect four ################################################################################
 
 impl ConnectFour {
-    pub fn width() -> usize { 7 }
-    pub fn height() -> usize { 6 }
-    pub fn walkup() -> Vec<usize> { vec![0,1,2,3,4,5,6] }
-    pub fn walkdown() -> Vec<usize> { vec![6,5,4,3,2,1,0] }
-    
+    pub fn width(&self) -> usize { self.field.len() }
+    pub fn height(&self) -> usize { self.height }
+    pub fn walkup(&self) -> Vec<usize> { (0..self.width()).collect() }
+    pub fn walkdown(&self) -> Vec<usize> { (0..self.width()).rev().collect() }
+
+    // a standard board of seven columns with six cells each
     pub fn new() -> Self {
+        ConnectFour::with_dimensions(STANDARD_WIDTH, STANDARD_HEIGHT).unwrap()
+    }
+
+    pub fn with_dimensions(width: usize, height: usize) -> Result<Self, GameError> {
+        ConnectFour::with_rules(width, height, STANDARD_WIN_LENGTH)
+    }
+
+    // connect win_length stones to win, at least two and no more than fit on the board
+    pub fn with_rules(width: usize, height: usize, win_length: usize) -> Result<Self, GameError> {
+        if width == 0 || width > MAX_WIDTH || height == 0 {
+            return Err(GameError::InvalidDimensions(width, height));
+        }
+        if win_length < 2 || win_length > max(width, height) {
+            return Err(GameError::InvalidWinLength(win_length));
+        }
         let mut cf = ConnectFour{
-            field: Vec::with_capacity(ConnectFour::width()),
+            field: Vec::with_capacity(width),
+            height,
+            win_length,
+            next: None,
+            status: GameStatus::InProgress,
+            permissive: true,
+            pop_out: false,
+            history: Vec::new(),
+            statuses: Vec::new(),
+            keys: Keys::new(width, height),
         };
-        for _coln in 0..ConnectFour::width() {
-            let col:Vec<Option<Player>> = Vec::with_capacity(ConnectFour::height());
+        for _coln in 0..width {
+            let col:Vec<Option<Player>> = Vec::with_capacity(height);
             cf.field.push(col);
         };
-        cf
+        Ok(cf)
+    }
+
+    // a game played by the rules: the players take turns and nobody moves once the game is over.
+    // strategies try out stones of both players, they need a permissive copy.
+    pub fn strict() -> Self {
+        ConnectFour::strict_with_rules(STANDARD_WIDTH, STANDARD_HEIGHT, STANDARD_WIN_LENGTH).unwrap()
+    }
+
+    pub fn strict_with_rules(width: usize, height: usize, win_length: usize) -> Result<Self, GameError> {
+        let mut cf = ConnectFour::with_rules(width, height, win_length)?;
+        cf.permissive = false;
+        Ok(cf)
+    }
+
+    pub fn is_permissive(&self) -> bool {
+        self.permissive
+    }
+
+    pub fn set_permissive(&mut self, permissive: bool) {
+        self.permissive = permissive;
+    }
+
+    pub fn set_pop_out(&mut self, pop_out: bool) {
+        self.pop_out = pop_out;
+    }
+
+    // a replicated game has the moves in the order its plan was read
+    pub fn history(&self) -> &[(Player, MoveKind, Column)] {
+        &self.history
+    }
+
+    // the moves as a string of column digits counted from 1, "4453" for instance.
+    // None if there are moves the notation has no digits for: pops, gray stones,
+    // columns beyond the ninth or a player moving twice in a row.
+    pub fn move_string(&self) -> Option<String> {
+        let mut moves = String::new();
+        let mut last: Option<&Player> = None;
+        for (p, kind, column) in &self.history {
+            if *p == Player::Gray || *kind != MoveKind::Drop || last == Some(p) {
+                return None;
+            }
+            moves.push(std::char::from_digit(column.to_usize() as u32 + 1, 10)?);
+            last = Some(p);
+        }
+        Some(moves)
+    }
+
+    // plays the moves of a string of column digits, the players taking turns.
+    // the next player starts, White if nobody has moved yet.
+    // errors tell the character, counted from 1, in the first line, and leave the game as it was
+    pub fn play_move_string(&mut self, moves: &str) -> Result<(), GameError> {
+        let parse_error = |i: usize, message: String| {
+            GameError::Parse { line: 1, column: i + 1, message }
+        };
+        // the moves are played on a copy, it replaces the game once all of them are played
+        let mut game = self.clone();
+        let mut p = game.next.clone().unwrap_or(Player::White);
+        for (i, c) in moves.chars().enumerate() {
+            let column = match c.to_digit(10) {
+                Some(d) if d > 0 && (d as usize) <= game.width() => Column::from_usize(d as usize - 1)?,
+                // the columns are counted from 1 in the string
+                Some(d) if d > 0 => return Err(parse_error(i, format!("there is no column {}", d))),
+                _ => return Err(parse_error(i, format!("unknown move '{}'", c))),
+            };
+            if game.status != GameStatus::InProgress {
+                return Err(parse_error(i, GameError::GameOver.to_string()));
+            }
+            game.drop_stone(&p, column).map_err(|e| { parse_error(i, e.to_string()) })?;
+            p = p.opponent().clone();
+        }
+        *self = game;
+        Ok(())
+    }
+
+    pub fn from_move_string(moves: &str) -> Result<Self, GameError> {
+        let mut g = ConnectFour::new();
+        g.play_move_string(moves)?;
+        Ok(g)
+    }
+
+    pub fn next_player(&self) -> Option<&Player> {
+        self.next.as_ref()
+    }
+
+    pub fn status(&self) -> &GameStatus {
+        &self.status
+    }
+
+    fn scan_status(&self) -> GameStatus {
+        for (n, col) in self.field.iter().enumerate() {
+            for (m, stone) in col.iter().enumerate() {
+                match stone {
+                    Some(Player::Black) | Some(Player::White) if !self.lines_through(n, m).is_empty() => {
+                        return GameStatus::Won(stone.clone().unwrap());
+                    },
+                    _ => (),
+                }
+            }
+        }
+        if self.move_possible() { GameStatus::InProgress } else { GameStatus::Draw }
     }
 
     pub fn dropped_stones(&self) -> usize {
@@ -154,10 +497,19 @@ impl ConnectFour {
 
     pub fn clone(&self) -> ConnectFour {
         let mut cf = ConnectFour{
-            field: Vec::with_capacity(ConnectFour::width()),
+            field: Vec::with_capacity(self.width()),
+            height: self.height,
+            win_length: self.win_length,
+            next: self.next.clone(),
+            status: self.status.clone(),
+            permissive: self.permissive,
+            pop_out: self.pop_out,
+            history: self.history.clone(),
+            statuses: self.statuses.clone(),
+            keys: self.keys.clone(),
         };
         for self_col in &self.field {
-            let mut col:Vec<Option<Player>> = Vec::with_capacity(ConnectFour::height());
+            let mut col:Vec<Option<Player>> = Vec::with_capacity(self.height);
             for player_option in self_col {
                 col.push(match player_option {
                     Some(player) => match player {
@@ -173,35 +525,185 @@ impl ConnectFour {
         cf
     }
 
-    pub fn replicate_game(plan: &str) -> Self {
-        let mut g = ConnectFour::new();
+    // reads a plan of the field, a line per column between two "------" lines.
+    // errors tell the line and the character, both counted from 1
+    pub fn replicate_game(plan: &str) -> Result<Self, GameError> {
+        ConnectFour::replicate_game_with_dimensions(plan, STANDARD_WIDTH, STANDARD_HEIGHT)
+    }
+
+    pub fn replicate_game_with_dimensions(plan: &str, width: usize, height: usize) -> Result<Self, GameError> {
+        ConnectFour::replicate_game_with_rules(plan, width, height, STANDARD_WIN_LENGTH)
+    }
+
+    pub fn replicate_game_with_rules(plan: &str, width: usize, height: usize, win_length: usize)
+    -> Result<Self, GameError> {
+        let parse_error = |line: usize, column: usize, message: String| {
+            GameError::Parse { line: line + 1, column: column + 1, message }
+        };
+        let mut g = ConnectFour::with_rules(width, height, win_length)?;
         for (i, line) in plan.split("\n").enumerate() {
             match i {
-                b if (b > 0 && b < 8) => {
-                    for c in line.chars() {
-                        g.drop_stone(
-                            match c {
-                                'x' => &Player::Black,
-                                'o' => &Player::White,
-                                ':' => &Player::Gray,
-                                what => { println!("{}, {}", what, i); assert!(false); &Player::Black },
-                            },
-                            Column::from_usize(i-1)
-                        ).unwrap(); 
+                b if (b > 0 && b <= width) => {
+                    for (j, c) in line.chars().enumerate() {
+                        let p = match c {
+                            'x' => &Player::Black,
+                            'o' => &Player::White,
+                            ':' => &Player::Gray,
+                            what => return Err(parse_error(i, j, format!("unknown stone '{}'", what))),
+                        };
+                        g.drop_stone(p, Column::from_usize(i-1)?)
+                            .map_err(|e| { parse_error(i, j, e.to_string()) })?;
                     }
                 },
-                c if (c==0 || c ==8) => assert_eq!(line, "------"),
+                c if (c == 0 || c == width + 1) && line != "------" => {
+                    return Err(parse_error(i, 0, String::from("expected \"------\"")));
+                },
                 _ => (),
             }
         }
-        g
+        Ok(g)
+    }
+
+    // reads a position of the classic game on a board of the standard height and tells who is to move.
+    // the position is either a plan like replicate_game reads it, the width taken from the number of
+    // columns, or a single line of the columns separated by slashes, as written by position_string.
+    // the line may end in the stone of the player to move, " x" or " o", otherwise the player
+    // with fewer stones is to move, White if both have as many.
+    pub fn parse_position(text: &str) -> Result<(Self, Player), GameError> {
+        ConnectFour::parse_position_with_rules(text, STANDARD_HEIGHT, STANDARD_WIN_LENGTH)
+    }
+
+    // the same on a board of the given height and win length, the width is still the number of columns
+    pub fn parse_position_with_rules(text: &str, height: usize, win_length: usize) -> Result<(Self, Player), GameError> {
+        let parse_error = |line: usize, column: usize, message: String| {
+            GameError::Parse { line: line + 1, column: column + 1, message }
+        };
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        if text.is_empty() {
+            return Err(parse_error(0, 0, String::from("expected a position")));
+        }
+
+        // the stones of every column, with the line and the character they start at
+        let mut columns: Vec<(usize, usize, &str)> = Vec::new();
+        let mut side = None;
+        if text.starts_with('-') {
+            let lines: Vec<&str> = text.split('\n').collect();
+            let last = lines.len() - 1;
+            if lines[0] != "------" {
+                return Err(parse_error(0, 0, String::from("expected \"------\"")));
+            }
+            if last == 0 || lines[last] != "------" {
+                return Err(parse_error(cmp::max(last, 1), 0, String::from("expected \"------\"")));
+            }
+            for (i, line) in lines.iter().enumerate().take(last).skip(1) {
+                columns.push((i, 0, line));
+            }
+        } else {
+            let (stones, player) = match text.find(' ') {
+                Some(k) => (&text[..k], Some((k + 1, &text[k + 1..]))),
+                None => (text, None),
+            };
+            let mut start = 0;
+            for column in stones.split('/') {
+                columns.push((0, start, column));
+                start += column.len() + 1;
+            }
+            side = match player {
+                None => None,
+                Some((_, "x")) => Some(Player::Black),
+                Some((_, "o")) => Some(Player::White),
+                Some((k, what)) => return Err(parse_error(0, k, format!("unknown player '{}'", what))),
+            };
+        }
+
+        let mut g = ConnectFour::with_rules(columns.len(), height, win_length)?;
+        for (n, (i, start, stones)) in columns.into_iter().enumerate() {
+            for (j, c) in stones.chars().enumerate() {
+                let p = match c {
+                    'x' => &Player::Black,
+                    'o' => &Player::White,
+                    ':' => &Player::Gray,
+                    what => return Err(parse_error(i, start + j, format!("unknown stone '{}'", what))),
+                };
+                g.drop_stone(p, Column::from_usize(n)?)
+                    .map_err(|e| { parse_error(i, start + j, e.to_string()) })?;
+            }
+        }
+
+        // gray stones may have been anybody's
+        let count = |p: &Player| -> usize {
+            g.field.iter().map(|col| col.iter().filter(|x| x.as_ref() == Some(p)).count()).sum()
+        };
+        let (black, white, gray) = (count(&Player::Black), count(&Player::White), count(&Player::Gray));
+        if black > white + 1 + gray || white > black + 1 + gray {
+            return Err(GameError::Unbalanced { black, white });
+        }
+        g.status = g.scan_status();
+        if g.status != GameStatus::InProgress {
+            return Err(GameError::GameOver);
+        }
+        let to_move = match side {
+            Some(Player::White) if white > black => return Err(GameError::WrongTurn),
+            Some(Player::Black) if black > white => return Err(GameError::WrongTurn),
+            Some(p) => p,
+            None if black < white => Player::Black,
+            None => Player::White,
+        };
+        g.next = Some(to_move.clone());
+        Ok((g, to_move))
     }
 
-    fn get_score(&self, p: &Player, n: usize, m: usize) -> Result<Score, Withdraw> {
+    // the columns bottom up, separated by slashes, and the stone of the player to move if that is known.
+    // "/ox/x/o/// x" is read back by parse_position
+    pub fn position_string(&self) -> String {
+        let columns: Vec<String> = self.field.iter().map(|col| {
+            col.iter().map(|x| match x {
+                Some(Player::White) => 'o',
+                Some(Player::Black) => 'x',
+                _ => ':',
+            }).collect()
+        }).collect();
+        match self.next {
+            Some(Player::Black) => format!("{} x", columns.join("/")),
+            Some(Player::White) => format!("{} o", columns.join("/")),
+            _ => columns.join("/"),
+        }
+    }
+
+    // a pop may connect the stones of both players at once, the player who popped wins then.
+    // connecting the opponent's stones only loses.
+    fn pop(&mut self, p: &Player, n: usize) -> Result<Score, GameError> {
+        if !self.pop_out {
+            return Err(GameError::PopNotAllowed);
+        }
+        match self.field[n].first() {
+            None => return Err(GameError::EmptyColumn),
+            Some(Some(stone)) if stone == p && *p != Player::Gray => (),
+            _ => return Err(GameError::NotYourStone),
+        }
+        self.key_column(n, false);
+        self.field[n].remove(0);
+        self.key_column(n, true);
+
+        let mut theirs = false;
+        for m in 0..self.field[n].len() {
+            if !self.lines_through(n, m).is_empty() {
+                if self.field[n][m].as_ref() == Some(p) {
+                    return Ok(Score::Won(0));
+                }
+                theirs = true;
+            }
+        }
+        Ok(if theirs { Score::Lost(0) } else { Score::Undecided(0.5) })
+    }
+
+    fn get_score(&self, p: &Player, n: usize, m: usize) -> Result<Score, GameError> {
+        // stones of the player needed next to the new one
+        let needed = self.win_length - 1;
 
         // vertical
-        let below = self.matching_distance(vec![n,n,n], m, Step::Down, p);
-        if below >= 3 {
+        let below = self.matching_distance(vec![n; needed], m, Step::Down, p);
+        if below >= needed {
 //println!("{} below {}", below, m);
             return Ok(Score::Won(0))
         }
@@ -211,7 +713,7 @@ impl ConnectFour {
         let right = self.matching_distance(iter, m, Step::Plane, p);
         let iter:Vec<usize> = (n+1..self.field.len()).collect();
         let left = self.matching_distance(iter, m, Step::Plane, p);
-        if left + right >= 3 {
+        if left + right >= needed {
 //println!("left {}, right {}", left, right);
             return Ok(Score::Won(0))
         }
@@ -221,7 +723,7 @@ impl ConnectFour {
         let right = self.matching_distance(iter, m, Step::Up, p);
         let iter:Vec<usize> = (n+1..self.field.len()).collect();
         let left = self.matching_distance(iter, m, Step::Down, p);
-        if left + right >= 3 {
+        if left + right >= needed {
 //println!("\\left {}, right {}", left, right);
             return Ok(Score::Won(0))
         }
@@ -231,7 +733,7 @@ impl ConnectFour {
         let right = self.matching_distance(iter, m, Step::Down, p);
         let iter:Vec<usize> = (n+1..self.field.len()).collect();
         let left = self.matching_distance(iter, m, Step::Up, p);
-        if left + right >= 3 {
+        if left + right >= needed {
 //println!("/left {}, right {}", left, right);
             return Ok(Score::Won(0))
         }
@@ -280,17 +782,159 @@ impl ConnectFour {
         distance-1
     }
 
-    pub fn drop_stone(&mut self, p: &Player, c:Column) -> Result<Score, Withdraw> {
-        self.make_move(&p, Rc::new(ConnectFourMove { data: c }))
+    // the lines of win_length or more connected stones through the top stone of the column,
+    // given as (column, row) coordinates from one end to the other, row 0 at the bottom.
+    // there may be several of them after a winning move.
+    pub fn winning_lines(&self, c: &Column) -> Vec<Vec<(usize,usize)>> {
+        let n = c.to_usize();
+        match self.field.get(n).map(|col| col.len()) {
+            Some(m) if m > 0 => self.lines_through(n, m-1),
+            _ => vec![],
+        }
     }
 
-    pub fn undrop_stone(&mut self, p: &Player, c:Column) {
-        self.withdraw_move(&p, Rc::new(ConnectFourMove { data: c }))
+    // the lines of the player through any stone of the column, after a pop has moved them all
+    pub fn lines_in_column(&self, c: &Column, p: &Player) -> Vec<Vec<(usize,usize)>> {
+        let n = c.to_usize();
+        let mut lines: Vec<Vec<(usize,usize)>> = Vec::new();
+        for m in 0..self.field.get(n).map_or(0, |col| col.len()) {
+            if self.field[n][m].as_ref() != Some(p) { continue; }
+            for line in self.lines_through(n, m) {
+                // a vertical line runs through several stones of the column
+                if !lines.contains(&line) {
+                    lines.push(line);
+                }
+            }
+        }
+        lines
+    }
+
+    fn lines_through(&self, n: usize, m: usize) -> Vec<Vec<(usize,usize)>> {
+        let p = match &self.field[n][m] {
+            Some(Player::Gray) | None => return vec![],
+            Some(p) => p,
+        };
+        let owned = |i: i32, j: i32| -> bool {
+            i >= 0 && j >= 0 && (i as usize) < self.field.len() && (j as usize) < self.field[i as usize].len()
+                && self.field[i as usize][j as usize].as_ref() == Some(p)
+        };
+
+        let mut lines = Vec::new();
+        // vertical, horizontal, diagonal (/) and diagonal (\)
+        for (di, dj) in [(0, 1), (1, 0), (1, 1), (1, -1)].iter() {
+            let (mut i, mut j) = (n as i32, m as i32);
+            while owned(i - di, j - dj) {
+                i -= di;
+                j -= dj;
+            }
+            let mut length = 0;
+            while owned(i + length * di, j + length * dj) {
+                length += 1;
+            }
+            if length >= self.win_length as i32 {
+                lines.push((0..length).map(|k| ((i + k * di) as usize, (j + k * dj) as usize)).collect());
+            }
+        }
+        lines
+    }
+
+    // like display, but the stones at the given cells are shown as capital letters
+    pub fn display_highlighted(&self, cells: &[(usize,usize)]) -> String {
+        let mut s = String::new();
+        s.push_str("------\n");
+        for (n, c) in self.field.iter().enumerate() {
+            for (m, x) in c.iter().enumerate() {
+                let stone = match x {
+                    Some(Player::White) => 'o',
+                    Some(Player::Black) => 'x',
+                    Some(Player::Gray) => ':',
+                    None => continue,
+                };
+                s.push(if cells.contains(&(n, m)) { stone.to_ascii_uppercase() } else { stone });
+            }
+            s.push('\n');
+        }
+        s.push_str("------");
+        s
+    }
+
+    pub fn drop_stone(&mut self, p: &Player, c:Column) -> Result<Score, GameError> {
+        self.make_move(&p, Arc::new(ConnectFourMove { data: c }))
+    }
+
+    pub fn undrop_stone(&mut self, p: &Player, c:Column) -> Result<(), GameError> {
+        self.withdraw_move(&p, Arc::new(ConnectFourMove { data: c }))
+    }
+
+    pub fn pop_stone(&mut self, p: &Player, c:Column) -> Result<Score, GameError> {
+        self.make_move(p, Arc::new(PopOutMove { data: c }))
+    }
+
+    pub fn unpop_stone(&mut self, p: &Player, c:Column) -> Result<(), GameError> {
+        self.withdraw_move(p, Arc::new(PopOutMove { data: c }))
+    }
+
+    // the index of a column that is on the board
+    fn column_index(&self, c: &Column) -> Result<usize, GameError> {
+        let n = c.to_usize();
+        if n < self.field.len() { Ok(n) } else { Err(GameError::InvalidColumn(n)) }
+    }
+
+    // the hash and whether it was taken from the other side, the same as bruteforce::hash_from_state
+    // finds for the field but at no cost. None on a board of more than bruteforce::MAX_CELLS cells
+    pub fn hash(&self) -> Option<(i128, bool)> {
+        self.keys.as_ref().map(|keys| keys.hash())
+    }
+
+    fn key_stone(&mut self, n: usize, m: usize, stone: &Player, add: bool) {
+        if let Some(keys) = self.keys.as_mut() {
+            keys.toggle(n, m, stone, add);
+        }
+    }
+
+    // the stones of a column move when a stone is popped or pushed back in from below
+    fn key_column(&mut self, n: usize, add: bool) {
+        for m in 0..self.field[n].len() {
+            if let Some(stone) = self.field[n][m].clone() {
+                self.key_stone(n, m, &stone, add);
+            }
+        }
+    }
+
+    // grays every stone that can no longer be part of a line of its player's,
+    // until graying them leaves no more stones dead
+    pub fn shade_dead_stones(&mut self) {
+        loop {
+            let mut dead = Vec::new();
+            for (n, col) in self.field.iter().enumerate() {
+                for (m, stone) in col.iter().enumerate() {
+                    match stone {
+                        Some(Player::Gray) | None => (),
+                        Some(p) => if self.is_dead(&n, &m, p.opponent()) { dead.push((n, m)); },
+                    }
+                }
+            }
+            if dead.is_empty() {
+                break;
+            }
+            for (n, m) in dead {
+                self.set_stone(n, m, Player::Gray);
+            }
+        }
+    }
+
+    // a stone turned into another one, shading turns them gray and back
+    fn set_stone(&mut self, n: usize, m: usize, stone: Player) {
+        if let Some(old) = self.field[n][m].clone() {
+            self.key_stone(n, m, &old, false);
+        }
+        self.key_stone(n, m, &stone, true);
+        self.field[n][m] = Some(stone);
     }
 
     fn move_possible(&self) -> bool {
         for col in &self.field {
-            if col.len() < ConnectFour::height() {
+            if col.len() < self.height {
                 return true;
             }
         }
@@ -301,7 +945,7 @@ impl ConnectFour {
         let n = n as i8;
         let m = m as i8;
         let mut x = Vec::new();
-        for i in 1..4 {
+        for i in 1..self.win_length as i8 {
             x.push((n, m-i));
             x.push((n, m+i));
             x.push((n-i, m-i));
@@ -312,15 +956,16 @@ impl ConnectFour {
             x.push((n+i, m+i));
         }
         x.into_iter()
-         .filter(|(a,b)| { *a>=0 && *a< ConnectFour::width() as i8
-                        && *b>=0 && *b< ConnectFour::height() as i8})
+         .filter(|(a,b)| { *a>=0 && *a< self.width() as i8
+                        && *b>=0 && *b< self.height as i8})
          .map(|(a,b)| { (a as usize, b as usize) })
          .collect()
     }
 
     fn is_dead(&self, n:&usize, m:&usize, tabu:&Player) -> bool {
-        let h = ConnectFour::height();
-        let w = ConnectFour::width();
+        let h = self.height;
+        let w = self.width();
+        let k = self.win_length;
 
         let killer = |x:&Option<Player>| -> bool {
             match x {
@@ -340,9 +985,9 @@ impl ConnectFour {
 //}
             let mut maxl=0;
             let mut distance = match dn {
-                1 => min(4, w-n),
-                -1 => min(4, n+1),
-                0 => 4,
+                1 => min(k, w-n),
+                -1 => min(k, n+1),
+                0 => k,
                 _ => panic!("???"),
             };
             distance = match dm {
@@ -375,25 +1020,25 @@ impl ConnectFour {
         let mut maxl = 1;
         maxl += count_options(*n,*m,1,0);
         maxl += count_options(*n,*m,-1,0);
-        if maxl >= 4 { return false; }
+        if maxl >= k as i8 { return false; }
 
         //vertical
         let mut maxl = 1;
         maxl += count_options(*n,*m,0,1);
         maxl += count_options(*n,*m,0,-1);
-        if maxl >= 4 { return false; }
+        if maxl >= k as i8 { return false; }
 
         //diagonal '/'
         let mut maxl = 1;
         maxl += count_options(*n,*m,1,1);
         maxl += count_options(*n,*m,-1,-1);
-        if maxl >= 4 { return false; }
+        if maxl >= k as i8 { return false; }
         
         //diagonal '\'
         let mut maxl = 1;
         maxl += count_options(*n,*m,-1,1);
         maxl += count_options(*n,*m,1,-1);
-        if maxl >= 4 { return false; }
+        if maxl >= k as i8 { return false; }
 
         true
     }
@@ -401,13 +1046,13 @@ impl ConnectFour {
     pub fn make_shading_move(
             &mut self, 
             p: &Player, 
-            mv: Rc<dyn Move<Column>>
-        ) -> Result<(Score, Vec<(usize,usize)>), Withdraw> {
-        let n = mv.data().to_usize();
+            mv: Arc<dyn Move<Column>>
+        ) -> Result<(Score, Vec<(usize,usize)>), GameError> {
+        let n = self.column_index(mv.data())?;
         let m = self.field[n].len();
-        if ConnectFour::height() == m {
+        if self.height == m {
             // column is obviously already filled to the top
-            Err(Withdraw::NotAllowed)
+            Err(GameError::ColumnFull)
         } else {
             // drop the stone
             self.field[n].push(match p {
@@ -415,11 +1060,12 @@ impl ConnectFour {
                 Player::Black => Some(Player::Black),
                 Player::Gray => Some(Player::Gray),                
             });
+            self.key_stone(n, m, p, true);
 
             // gray this very stone too if dropped onto a dead position,
             // but no need to include it in the result, it'll be removed when it comes to unshading
             if self.is_dead(&n, &m, p.opponent()) {
-                self.field[n][m] = Some(Player::Gray);
+                self.set_stone(n, m, Player::Gray);
             }
 
             // gray opponent's grayable stones
@@ -441,7 +1087,7 @@ impl ConnectFour {
 
             // turn them gray
             let grayed = grayable.into_iter().map(|(a,b)| {
-                self.field[a][b] = Some(Player::Gray);
+                self.set_stone(a, b, Player::Gray);
                 (a,b)
             }).collect();
             // return the score
@@ -465,7 +1111,7 @@ impl ConnectFour {
     
     pub fn withdraw_move_unshading(&mut self,
             p: &Player,
-            mv: Rc<dyn Move<Column>>,
+            mv: Arc<dyn Move<Column>>,
             ungrayable: Vec<(usize,usize)>) {
         let n = mv.data().to_usize();
         if self.field[n].len() == 0 {
@@ -473,12 +1119,14 @@ impl ConnectFour {
         }
 
         // un-drop the stone
-        self.field[n].pop();
+        if let Some(Some(stone)) = self.field[n].pop() {
+            self.key_stone(n, self.field[n].len(), &stone, false);
+        }
 
         // turn them back in the game
         ungrayable.into_iter().for_each(|(a,b)| {
 //println!("ungray {} {} {:?}\n{}", n, m, p, self.display());
-            self.field[a][b] = Some(p.opponent().clone());
+            self.set_stone(a, b, p.opponent().clone());
 //println!("->\n{}", self.display());
         });
 //1: looking for lost balance
@@ -503,6 +1151,12 @@ pub struct ConnectFourStrategy {
     pub my_tabu_koeff: f32,
     pub opp_tabu_koeff: f32,
     pub tabu_defense_koeff: f32,
+    // the search is split into this many threads
+    pub nthreads: usize,
+    // the positions of the first plies the book has are looked up rather than searched
+    pub book: Option<Arc<OpeningBook>>,
+    // the positions with enough stones for the table are looked up rather than searched
+    pub endgame: Option<Arc<EndgameTable>>,
 }
 
 enum Cell {
@@ -515,8 +1169,8 @@ enum Cell {
 impl ConnectFourStrategy {
     #[allow(dead_code)]
     fn display_efield(&self, ef: &Vec<Vec<Cell>>) {
-        for j in (0..ConnectFour::height()).rev() {
-            for i in 0..ConnectFour::width() {
+        for j in (0..ef[0].len()).rev() {
+            for i in 0..ef.len() {
                 print!("{}", match ef[i][j] {
                     Cell::N => ".",
                     Cell::M => "m",
@@ -564,18 +1218,31 @@ impl ConnectFourStrategy {
 use std::cmp;
 impl Strategy<Column,Vec<Vec<Option<Player>>>> for ConnectFourStrategy {
     
-    fn evaluate_move(&self, g: Rc<RefCell<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
-                     p: &Player, mv: Rc<dyn Move<Column>>) 
-    -> Result<f32, Withdraw> {
+    fn evaluate_move(&self, g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+                     p: &Player, mv: Arc<dyn Move<Column>>) 
+    -> Result<f32, GameError> {
+        // the tabus are found by trying out the stones of both players
+        let g = permissive(g);
+        let (width, height) = g.read().unwrap().dimensions();
+        let win_length = g.read().unwrap().win_length();
         let n = mv.data().to_usize();
-        let m = g.borrow().state()[n].len();
-        if m >= ConnectFour::height() { return Err(Withdraw::NotAllowed); }
+        if n >= width { return Err(GameError::InvalidColumn(n)); }
+        if mv.kind() == MoveKind::Pop {
+            // a pop takes no cell, it is judged by the tabus it leaves behind
+            let ground_score = self.tabu_score(Arc::clone(&g), p);
+            g.write().unwrap().make_move(p, Arc::clone(&mv))?;
+            let offense_score = self.tabu_score(Arc::clone(&g), p) - ground_score;
+            g.write().unwrap().withdraw_move(p, mv).unwrap();
+            return Ok(offense_score);
+        }
+        let m = g.read().unwrap().state()[n].len();
+        if m >= height { return Err(GameError::ColumnFull); }
 
         // fill evaluation field with empty cells
-        let mut efield = Vec::with_capacity(ConnectFour::width());
-        for _ in 0..ConnectFour::width() {
-            let mut ecol = Vec::with_capacity(ConnectFour::height());
-            for _ in 0..ConnectFour::height() {
+        let mut efield = Vec::with_capacity(width);
+        for _ in 0..width {
+            let mut ecol = Vec::with_capacity(height);
+            for _ in 0..height {
                 ecol.push(Cell::N);
             }
             efield.push(ecol);
@@ -587,7 +1254,7 @@ impl Strategy<Column,Vec<Vec<Option<Player>>>> for ConnectFourStrategy {
         let white = |player: &Player| { match player {
             Player::White => Cell::M, Player::Black => Cell::O, Player::Gray => Cell::D }};
         let mut i:usize = 0;
-        for c in g.borrow().state() { // that's the current Connect Four field
+        for c in g.read().unwrap().state() { // that's the current Connect Four field
             let mut j:usize = 0;
             for f in c {
                 match f {
@@ -602,13 +1269,24 @@ impl Strategy<Column,Vec<Vec<Option<Player>>>> for ConnectFourStrategy {
         }
         
         // identify dead cells
-        let efield = self.fill_in_dead_cells(Rc::clone(&g), efield);
+        let efield = self.fill_in_dead_cells(Arc::clone(&g), efield);
 
         // calculate score
-        let total_score = self.positional_score(n, m, &efield)
+        let total_score = self.positional_score(n, m, &efield, win_length)
                         + self.tabu_diff_score(g, p, mv);
         Ok(total_score)
     }
+
+    fn threads(&self) -> usize {
+        self.nthreads
+    }
+
+    fn known_move(&self, g: &dyn Game<Column,Vec<Vec<Option<Player>>>>, p: &Player)
+    -> Option<(Arc<dyn Move<Column>>, Score)> {
+        let book_move = self.book.as_ref().and_then(|book| book.probe(g, p));
+        let (column, score) = book_move.or_else(|| self.endgame.as_ref()?.probe(g, p))?;
+        Some((Arc::new(ConnectFourMove { data: column }), score))
+    }
 }
 
 #[derive(Debug)]
@@ -627,29 +1305,40 @@ impl ConnectFourStrategy {
             my_tabu_koeff: -10.0,
             opp_tabu_koeff: 10.0,
             tabu_defense_koeff: 0.25,
+            nthreads: 1,
+            book: None,
+            endgame: None,
+        }
+    }
+
+    pub fn new(nthreads:usize) -> Self {
+        ConnectFourStrategy {
+            nthreads,
+            ..ConnectFourStrategy::default()
         }
     }
 
     fn fill_in_dead_cells(&self, 
-            g: Rc<RefCell<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
             mut efield: Vec<Vec<Cell>>)  -> Vec<Vec<Cell>> {
-        let mut mutable_game = g.borrow_mut();
+        let mut mutable_game = g.write().unwrap();
+        let (width, height) = mutable_game.dimensions();
         
         let mut cp = &Player::White;
-        for col in 0..ConnectFour::width() {
+        for col in 0..width {
             // look for mutual tabus
-            let mv = Rc::new(ConnectFourMove {
-                data: Column::from_usize(col) 
+            let mv = Arc::new(ConnectFourMove {
+                data: Column::from_usize(col).unwrap()
             });
             let mut i = 0;
             while let Ok(score) = mutable_game.make_move(cp, mv.clone()) {
                 i += 1;
                 if let Score::Won(_) = score {
-                    mutable_game.withdraw_move(cp, mv.clone());
+                    mutable_game.withdraw_move(cp, mv.clone()).unwrap();
                     cp = cp.opponent();
                     // unwrap in the next line assumed to be save because of the preceding withdrawal
                     if let Score::Won(_) = mutable_game.make_move(cp, mv.clone()).unwrap() {
-                        for j in mutable_game.state()[col].len()..ConnectFour::height() {
+                        for j in mutable_game.state()[col].len()..height {
                             efield[col][j] = Cell::D;
                         }
                         break;
@@ -661,7 +1350,7 @@ impl ConnectFourStrategy {
             //println!("{}", mutable_game.display());
             for _ in 0..i {
                 cp = cp.opponent();
-                mutable_game.withdraw_move(cp, mv.clone());
+                mutable_game.withdraw_move(cp, mv.clone()).unwrap();
             }
         }
         efield
@@ -669,34 +1358,35 @@ impl ConnectFourStrategy {
 
     // comparing tabu rows before and after the move
     // panicks if the move is not allowed
-    fn tabu_diff_score(&self, g: Rc<RefCell<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
-                  p: &Player, mv: Rc<dyn Move<Column>>)  -> f32 {
-        let ground_score = self.tabu_score(Rc::clone(&g), p);
+    fn tabu_diff_score(&self, g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+                  p: &Player, mv: Arc<dyn Move<Column>>)  -> f32 {
+        let ground_score = self.tabu_score(Arc::clone(&g), p);
 
-        g.borrow_mut().make_move(p, Rc::clone(&mv)).unwrap();
-        let offense_score = self.tabu_score(Rc::clone(&g), p) - ground_score;     
-        g.borrow_mut().withdraw_move(p, Rc::clone(&mv));
+        g.write().unwrap().make_move(p, Arc::clone(&mv)).unwrap();
+        let offense_score = self.tabu_score(Arc::clone(&g), p) - ground_score;     
+        g.write().unwrap().withdraw_move(p, Arc::clone(&mv)).unwrap();
 
-        g.borrow_mut().make_move(p.opponent(), Rc::clone(&mv)).unwrap();
-        let defense_score = self.tabu_score(Rc::clone(&g), p) - ground_score;     
-        g.borrow_mut().withdraw_move(p.opponent(), Rc::clone(&mv));
+        g.write().unwrap().make_move(p.opponent(), Arc::clone(&mv)).unwrap();
+        let defense_score = self.tabu_score(Arc::clone(&g), p) - ground_score;     
+        g.write().unwrap().withdraw_move(p.opponent(), Arc::clone(&mv)).unwrap();
         
         offense_score - defense_score * self.tabu_defense_koeff
     }
 
-    fn tabu_score(&self, g: Rc<RefCell<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+    fn tabu_score(&self, g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
                   p: &Player)  -> f32 {
-        let mut mutable_game = g.borrow_mut();
+        let mut mutable_game = g.write().unwrap();
+        let (width, _) = mutable_game.dimensions();
         
-        (0..ConnectFour::width()) // loop over columns
+        (0..width) // loop over columns
         .map(|col| {
             // look for tabus
-            let mut tabu = Tabu{ column: Column::from_usize(col),
+            let mut tabu = Tabu{ column: Column::from_usize(col).unwrap(),
                                  mine: None, theirs: None, };
             let mut cp = p;
             let mut i = 0;
-            while let Ok(score) = mutable_game.make_move(cp, Rc::new(
-                    ConnectFourMove { data: Column::from_usize(col) })) {
+            while let Ok(score) = mutable_game.make_move(cp, Arc::new(
+                    ConnectFourMove { data: Column::from_usize(col).unwrap() })) {
                 cp = cp.opponent();
                 i += 1;
                 match score {
@@ -714,14 +1404,14 @@ impl ConnectFourStrategy {
             //println!("{}", mutable_game.display());
             for _ in 0..i {
                 cp = cp.opponent();
-                mutable_game.withdraw_move(cp, Rc::new(
-                    ConnectFourMove { data: Column::from_usize(col) }));
+                mutable_game.withdraw_move(cp, Arc::new(
+                    ConnectFourMove { data: Column::from_usize(col).unwrap() })).unwrap();
             }
 
             let mut cp = p.opponent();
             let mut i = 0;
-            while let Ok(score) = mutable_game.make_move(cp, Rc::new(
-                    ConnectFourMove { data: Column::from_usize(col) })) {
+            while let Ok(score) = mutable_game.make_move(cp, Arc::new(
+                    ConnectFourMove { data: Column::from_usize(col).unwrap() })) {
                 cp = cp.opponent();
                 i += 1;
                 match score {
@@ -739,8 +1429,8 @@ impl ConnectFourStrategy {
             //println!("{}", mutable_game.display());
             for _ in 0..i {
                 cp = cp.opponent();
-                mutable_game.withdraw_move(cp, Rc::new(
-                    ConnectFourMove { data: Column::from_usize(col) }));
+                mutable_game.withdraw_move(cp, Arc::new(
+                    ConnectFourMove { data: Column::from_usize(col).unwrap() })).unwrap();
             }
             
             if let Some(_) = tabu.mine {
@@ -764,58 +1454,61 @@ impl ConnectFourStrategy {
         .sum()
     }
     
-    // basically adding up the user's own potential for connecting four from/to 
+    // basically adding up the user's own potential for connecting win_length stones from/to
     // here and the opponents, weighed by the strategy's coefficients
-    fn positional_score(&self, n:usize, m:usize, efield:&Vec<Vec<Cell>>) -> f32 {
+    fn positional_score(&self, n:usize, m:usize, efield:&Vec<Vec<Cell>>, win_length:usize) -> f32 {
+        // the cells on either side that can be part of a line through this one
+        let reach = win_length - 1;
         let score_arithmetics = |((mfree_left, m_left, nm_left), (ofree_left, o_left, no_left)), 
                                 ((mfree_right,m_right,nm_right),(ofree_right,o_right,no_right))| -> f32 {
             let mut partial_score = 0.0;
-            if mfree_left + mfree_right >= 3 {
+            if mfree_left + mfree_right >= reach as i32 {
                 partial_score += self.mscore_koeff * (m_left + m_right) as f32;
                 partial_score += self.mscore_koeff * self.nscore_koeff * (nm_left + nm_right) as f32;
             }
-            if ofree_left + ofree_right >= 3 {
+            if ofree_left + ofree_right >= reach as i32 {
                 partial_score += self.oscore_koeff * (o_left + o_right) as f32;
                 partial_score += self.oscore_koeff * self.nscore_koeff * (no_left + no_right) as f32;
             }
             partial_score
         };
 
+        let (width, height) = (efield.len(), efield[0].len());
         let mut total_score = 0.0;
         // horizontal score
         let ontheleft = self.efield_counting(efield,
-            (match n { s if s < 3 => 0, b => b-3 }..n).rev().collect(),
-            vec!(m,m,m));
+            (n.saturating_sub(reach)..n).rev().collect(),
+            vec![m; reach]);
         let ontheright = self.efield_counting(efield,
-            (cmp::min(ConnectFour::width(), n+1)..cmp::min(ConnectFour::width(), n+4)).collect(),
-            vec!(m,m,m));
+            (cmp::min(width, n+1)..cmp::min(width, n+win_length)).collect(),
+            vec![m; reach]);
         total_score += score_arithmetics(ontheleft, ontheright);
 
         // diagonal score '/'
         let ontheleft = self.efield_counting(efield,
-            (match n { s if s < 3 => 0, b => b-3 }..n).rev().collect(),
-            (match m { s if s < 3 => 0, b => b-3 }..m).rev().collect());
+            (n.saturating_sub(reach)..n).rev().collect(),
+            (m.saturating_sub(reach)..m).rev().collect());
         let ontheright = self.efield_counting(efield,
-            (cmp::min(ConnectFour::width(), n+1)..cmp::min(ConnectFour::width(), n+4)).collect(),
-            (cmp::min(ConnectFour::height(),m+1)..cmp::min(ConnectFour::height(),m+4)).collect());
+            (cmp::min(width, n+1)..cmp::min(width, n+win_length)).collect(),
+            (cmp::min(height, m+1)..cmp::min(height, m+win_length)).collect());
         total_score += score_arithmetics(ontheleft, ontheright);
 
         // diagonal score '\'
         let ontheleft = self.efield_counting(efield,
-            (match n { s if s < 3 => 0, b => b-3 }..n).rev().collect(),
-            (cmp::min(ConnectFour::height(),m+1)..cmp::min(ConnectFour::height(),m+4)).collect());
+            (n.saturating_sub(reach)..n).rev().collect(),
+            (cmp::min(height, m+1)..cmp::min(height, m+win_length)).collect());
         let ontheright = self.efield_counting(efield,
-            (cmp::min(ConnectFour::width(), n+1)..cmp::min(ConnectFour::width(), n+4)).collect(),
-            (match m { s if s < 3 => 0, b => b-3 }..m).rev().collect());
+            (cmp::min(width, n+1)..cmp::min(width, n+win_length)).collect(),
+            (m.saturating_sub(reach)..m).rev().collect());
         total_score += score_arithmetics(ontheleft, ontheright);
 
         // vertical score
         let ontheleft = self.efield_counting(efield,
-            vec!(n,n,n),
-            (match m { s if s < 3 => 0, b => b-3 }..m).rev().collect());
+            vec![n; reach],
+            (m.saturating_sub(reach)..m).rev().collect());
         let ontheright = self.efield_counting(efield,
-            vec!(n,n,n),
-            (cmp::min(ConnectFour::height(), m+1)..cmp::min(ConnectFour::height(), m+4)).collect());
+            vec![n; reach],
+            (cmp::min(height, m+1)..cmp::min(height, m+win_length)).collect());
         total_score += score_arithmetics(ontheleft, ontheright);
 
         total_score
@@ -0,0 +1,188 @@
+//pub mod generic;
+use generic::{Game,GameError,Player,Score};
+use connectfour::{Column,ConnectFour};
+use bruteforce::{MAX_CELLS,StoredGame,read_store,swap,write_store};
+use book::PositionTable;
+use position::{PositionKey,Shading};
+use std::collections::{HashMap,HashSet};
+use std::path::Path;
+
+//#################################################################################################
+// endgame database
+//#################################################################################################
+
+// the exact best move of every position with at least so many stones that can be reached from a position,
+// found by walking the game forward and solving the positions backwards, the later ones first.
+// the file is kept like the game store.
+#[derive(Debug)]
+pub struct EndgameTable {
+    // width, height and win length of the board
+    rules: (usize,usize,usize),
+    // sorted by key and player, the column as seen from the side the key was taken from
+    entries: Vec<(PositionKey, Player, Score, Column)>,
+}
+
+impl EndgameTable {
+    pub fn rules(&self) -> (usize,usize,usize) {
+        self.rules
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // the positions of a game on an empty board with at least min_stones, either player moving first
+    pub fn generate(rules: (usize,usize,usize), min_stones: usize) -> Result<Self, GameError> {
+        let (width, height, win_length) = rules;
+        let game = ConnectFour::with_rules(width, height, win_length)?;
+        let mut walk = Walk::new(&game, min_stones)?;
+        for p in [Player::White, Player::Black].iter() {
+            walk.solve(&mut game.clone(), p);
+        }
+        Ok(walk.table())
+    }
+
+    // the positions with at least min_stones the game may come to, the player moving next.
+    // every position on the way is walked through, the later the game the sooner it is done
+    pub fn generate_from(game: &ConnectFour, p: &Player, min_stones: usize) -> Result<Self, GameError> {
+        // pops take stones away, the walk counts on them piling up
+        if game.pop_out() {
+            return Err(GameError::UnsupportedRules);
+        }
+        let mut walk = Walk::new(game, min_stones)?;
+        let mut game = game.clone();
+        game.set_permissive(true);
+        walk.solve(&mut game, p);
+        Ok(walk.table())
+    }
+
+    fn from_entries(rules: (usize,usize,usize), mut entries: Vec<(PositionKey, Player, Score, Column)>) -> Self {
+        entries.sort_by_key(|(key, p, _, _)| (*key, *p == Player::White));
+        EndgameTable { rules, entries }
+    }
+
+    // the best move of the player in the position of the key and its score, the column as seen from the key's side.
+    // the key is the one of the shaded position
+    pub fn lookup(&self, key: PositionKey, p: &Player) -> Option<(Column, Score)> {
+        let found = self.entries.binary_search_by_key(&(key, *p == Player::White), |(key, p, _, _)| (*key, *p == Player::White));
+        found.ok().map(|i| (self.entries[i].3.clone(), self.entries[i].2.clone()))
+    }
+
+    // the same for the game, shaded or not, the column as seen from the game's side
+    pub fn lookup_game(&self, game: &ConnectFour, p: &Player) -> Option<(Column, Score)> {
+        let (key, mirrored) = shaded_key(game)?;
+        self.lookup(key, p).map(|(column, score)| (swap(&column, mirrored, self.rules.0), score))
+    }
+
+    pub fn read(path: &Path, rules: (usize,usize,usize)) -> Result<Self, std::io::Error> {
+        let mut entries = Vec::new();
+        for game in read_store(path, rules)? {
+            let key = PositionKey::from_hash(game.hash, rules)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            if let Some(column) = game.column {
+                entries.push((key, game.player, game.score, column));
+            }
+        }
+        Ok(EndgameTable::from_entries(rules, entries))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), std::io::Error> {
+        let games: Vec<StoredGame> = self.entries.iter().map(|(key, player, score, column)| {
+            StoredGame { hash: key.hash(), player: player.clone(), score: score.clone(), column: Some(column.clone()) }
+        }).collect();
+        write_store(path, self.rules, &games)
+    }
+}
+
+impl PositionTable for EndgameTable {
+    fn probe(&self, game: &dyn Game<Column,Vec<Vec<Option<Player>>>>, p: &Player) -> Option<(Column, Score)> {
+        let (width, height, win_length) = self.rules;
+        if game.dimensions() != (width, height) || game.win_length() != win_length || game.pop_out() {
+            return None;
+        }
+        // the position is shaded on a copy of its own
+        let (key, mirrored) = PositionKey::of_game(game)?;
+        let copy = key.game(self.rules).ok()?;
+        self.lookup_game(&copy, p).map(|(column, score)| (swap(&column, mirrored, width), score))
+    }
+}
+
+// positions are kept with every stone gray that can no longer be part of a line, the way the brute force
+// search shades its games. positions that differ in dead stones only are decided the same way
+fn shaded_key(game: &ConnectFour) -> Option<(PositionKey, bool)> {
+    PositionKey::with_shading(game, Shading::Ignore)
+}
+
+// the positions reached so far, the solved ones and the ones too early for the table
+struct Walk {
+    rules: (usize,usize,usize),
+    min_stones: usize,
+    // the column as seen from the side the key was taken from
+    solved: HashMap<(PositionKey, Player), (Score, Column)>,
+    passed: HashSet<(PositionKey, Player)>,
+}
+
+impl Walk {
+    fn new(game: &ConnectFour, min_stones: usize) -> Result<Self, GameError> {
+        let (width, height) = game.dimensions();
+        if width * height > MAX_CELLS {
+            return Err(GameError::InvalidDimensions(width, height));
+        }
+        Ok(Walk { rules: (width, height, game.win_length()), min_stones, solved: HashMap::new(), passed: HashSet::new() })
+    }
+
+    // the score of the player's best move, if the position has enough stones for the table.
+    // the positions after every move are solved first
+    fn solve(&mut self, game: &mut ConnectFour, p: &Player) -> Option<Score> {
+        let (key, mirrored) = shaded_key(game).unwrap();
+        let in_table = game.dropped_stones() >= self.min_stones;
+        if in_table {
+            if let Some((score, _)) = self.solved.get(&(key, p.clone())) {
+                return Some(score.clone());
+            }
+        } else if !self.passed.insert((key, p.clone())) {
+            return None;
+        }
+        let mut best: Option<(Score, Column)> = None;
+        for n in 0..game.width() {
+            let column = Column::from_usize(n).unwrap();
+            let score = match game.drop_stone(p, column.clone()) {
+                Err(_) => continue,
+                Ok(Score::Undecided(_)) => self.solve(game, p.opponent()).map(|score| score.reverse()),
+                Ok(score) => Some(score),
+            };
+            game.undrop_stone(p, column.clone()).unwrap();
+            if let Some(score) = score {
+                if best.as_ref().is_none_or(|(known, _)| preference(&score) > preference(known)) {
+                    best = Some((score, column));
+                }
+            }
+        }
+        if !in_table {
+            return None;
+        }
+        // a full board has no move left
+        let (score, column) = best?;
+        self.solved.insert((key, p.clone()), (score.clone(), swap(&column, mirrored, self.rules.0)));
+        Some(score)
+    }
+
+    fn table(self) -> EndgameTable {
+        let entries = self.solved.into_iter().map(|((key, p), (score, column))| (key, p, score, column)).collect();
+        EndgameTable::from_entries(self.rules, entries)
+    }
+}
+
+// wins first, the sooner the better, then draws and then losses, the later the better
+fn preference(score: &Score) -> (u8, i64) {
+    match score {
+        Score::Won(in_n) => (3, -(*in_n as i64)),
+        Score::Remis(_) => (2, 0),
+        Score::Undecided(_) => (1, 0),
+        Score::Lost(in_n) => (0, *in_n as i64),
+    }
+}
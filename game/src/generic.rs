@@ -2,10 +2,11 @@
 // generic game with two players
 //#################################################################################################
 
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::sync::{Arc,RwLock};
+use std::time::{Duration,Instant};
+use std::thread;
 
-#[derive(PartialEq, Eq, PartialOrd, Debug, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Hash, Debug, Clone)]
 pub enum Player {
     Black,
     White,
@@ -32,9 +33,22 @@ impl std::fmt::Display for Player {
     }
 }
 
-pub trait Move<T> {
+// moves, games and strategies can be shared between threads
+pub trait Move<T>: Send + Sync {
     fn data(&self) -> &T;
     fn display(&self) -> String;
+    // moves of different kinds may carry the same data
+    fn kind(&self) -> MoveKind {
+        MoveKind::Drop
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum MoveKind {
+    // a stone is put on the board
+    Drop,
+    // a stone of the player's own is taken out of the bottom row
+    Pop,
 }
 
 #[derive(PartialEq, Debug, Clone)]
@@ -45,125 +59,467 @@ pub enum Score {
     Lost(u32),
 }
 
-#[derive(Debug)]
-pub enum Withdraw {
-    NotAllowed,
+// what a search found out about a position
+#[derive(Debug, Clone)]
+pub struct Analysis<T> {
+    // the score of every possible move, in the order of Game::possible_moves
+    pub scores: Vec<(T, Score)>,
+    // the best move followed by the moves both players are expected to make
+    pub variation: Vec<T>,
+}
+
+// what can go wrong when moves are made, taken back or read in
+#[derive(PartialEq, Debug, Clone)]
+pub enum GameError {
+    // the column is filled to the top
+    ColumnFull,
+    // there is no column with this number on the board
+    InvalidColumn(usize),
+    // there is no stone to take back in the column
+    EmptyColumn,
+    // the game is won or drawn already
+    GameOver,
+    // the rules of the game do not allow popping stones
+    PopNotAllowed,
+    // only the player's own stones can be popped
+    NotYourStone,
+    // it is the other player's turn
+    WrongTurn,
+    // there is no board of this width and height
+    InvalidDimensions(usize, usize),
+    // so many stones cannot be connected on the board, or connecting them is no game
+    InvalidWinLength(usize),
+    // a position could not be read, line and column count from 1
+    Parse { line: usize, column: usize, message: String },
+    // the players cannot have taken turns to drop that many stones
+    Unbalanced { black: usize, white: usize },
+    // the number is no position key for the board
+    InvalidKey(i128),
+    // the strategy cannot play by the rules of the game, like the solver on a board of another size
+    UnsupportedRules,
+    // in a game played by the rules, only the last move can be taken back
+    NotLastMove,
+    // the strategy found no best move, the position cannot come up in a game
+    NoSolution,
+}
+
+impl std::fmt::Display for GameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            GameError::ColumnFull => write!(f, "column is full"),
+            GameError::InvalidColumn(n) => write!(f, "there is no column {}", n),
+            GameError::EmptyColumn => write!(f, "column is empty"),
+            GameError::GameOver => write!(f, "game is over"),
+            GameError::PopNotAllowed => write!(f, "stones cannot be popped in this game"),
+            GameError::NotYourStone => write!(f, "the bottom stone is not yours"),
+            GameError::WrongTurn => write!(f, "it is the other player's turn"),
+            GameError::InvalidDimensions(width, height) => write!(f, "there is no {}x{} board", width, height),
+            GameError::InvalidWinLength(n) => write!(f, "there is no connect {} on this board", n),
+            GameError::Parse { line, column, message } => write!(f, "line {}, column {}: {}", line, column, message),
+            GameError::Unbalanced { black, white } => write!(f, "{} black and {} white stones cannot have taken turns", black, white),
+            GameError::InvalidKey(key) => write!(f, "{} is not the key of a position", key),
+            GameError::UnsupportedRules => write!(f, "the strategy does not play by the rules of this game"),
+            GameError::NotLastMove => write!(f, "only the last move can be taken back"),
+            GameError::NoSolution => write!(f, "there is no best move in this position"),
+        }
+    }
 }
 
-pub trait Game<T,S> {
-    fn possible_moves(&self, p: &Player) -> Vec<Rc<dyn Move<T>>>;
-    fn make_move(&mut self, p: &Player, m: Rc<dyn Move<T>>) -> Result<Score, Withdraw>;
-    fn withdraw_move(&mut self, p: &Player, m: Rc<dyn Move<T>>);
+impl std::error::Error for GameError {}
+
+pub trait Game<T,S>: Send + Sync {
+    fn possible_moves(&self, p: &Player) -> Vec<Arc<dyn Move<T>>>;
+    fn make_move(&mut self, p: &Player, m: Arc<dyn Move<T>>) -> Result<Score, GameError>;
+    fn withdraw_move(&mut self, p: &Player, m: Arc<dyn Move<T>>) -> Result<(), GameError>;
     fn display(&self) -> String;
     fn state(&self) -> &S;
+    // width and height of the board the game is played on
+    fn dimensions(&self) -> (usize, usize);
+    // the number of stones in a row that win the game
+    fn win_length(&self) -> usize;
+    // whether the players may pop their own stones out of the bottom row
+    fn pop_out(&self) -> bool {
+        false
+    }
+
+    // an independent copy of the game for searching in another thread, if it can be copied.
+    // the copy is permissive, searches try out the stones of both players
+    fn replicate(&self) -> Option<Arc<RwLock<dyn Game<T,S>>>> {
+        None
+    }
+
+    // whether the game takes any move at any time, regardless of turns and the end of the game
+    fn is_permissive(&self) -> bool {
+        true
+    }
+
+    // the most moves the game may still last, None if it may go on for ever
+    fn moves_left(&self) -> Option<usize> {
+        None
+    }
 }
 
 //### strategy ####################################################################################
 
-pub trait Strategy<T,S> {
-    fn evaluate_move(&self, g: Rc<RefCell<dyn Game<T,S>>>, p: &Player, m: Rc<dyn Move<T>>) -> Result<f32, Withdraw>;
+pub trait Strategy<T,S>: Send + Sync {
+    fn evaluate_move(&self, g: Arc<RwLock<dyn Game<T,S>>>, p: &Player, m: Arc<dyn Move<T>>) -> Result<f32, GameError>;
+
+    // number of threads find_best_move may split its search into
+    fn threads(&self) -> usize {
+        1
+    }
+
+    // the best move of the player and its score if they are known without searching,
+    // from a table of solved positions for instance. the search asks for every position it comes to
+    fn known_move(&self, _g: &dyn Game<T,S>, _p: &Player) -> Option<(Arc<dyn Move<T>>, Score)> {
+        None
+    }
 
     fn find_best_move(&self, 
-            g: Rc<RefCell<dyn Game<T,S>>>,
+            g: Arc<RwLock<dyn Game<T,S>>>,
+            p: &Player,
+            moves_ahead: i32,
+            game_evaluation: bool
+        ) -> (Option<Arc<dyn Move<T>>>, Option<Score>) {
+        search_best_move(self, permissive(g), p, moves_ahead, game_evaluation)
+    }
+
+    // iterative deepening: searches one move further ahead after the other and returns
+    // the best move of the last search that was completed within the time budget.
+    // strategies with a search of their own rather than look_ahead override it.
+    fn find_best_move_in_time(&self,
+            g: Arc<RwLock<dyn Game<T,S>>>,
+            p: &Player,
+            budget: Duration,
+            game_evaluation: bool
+        ) -> (Option<Arc<dyn Move<T>>>, Option<Score>) {
+        search_best_move_in_time(self, g, p, budget, game_evaluation)
+    }
+
+    // scores every possible move the way find_best_move compares them,
+    // in the order of Game::possible_moves. only drops are scored, the data
+    // of a move of another kind could not be told apart from a drop's.
+    fn analyze_position(&self,
+            g: Arc<RwLock<dyn Game<T,S>>>,
             p: &Player,
             moves_ahead: i32,
             game_evaluation: bool
-        ) -> (Option<Rc<dyn Move<T>>>, Option<Score>) {
-
-        //let mut win_option: Option<Rc<Move<T>>> = None;
-        let mut remis_option: Option<(Rc<dyn Move<T>>,u32)> = None;
-        let mut lost_options: Vec<(Rc<dyn Move<T>>,u32)> = Vec::new();
-        let mut undecided_options: Vec<(Rc<dyn Move<T>>, f32)> = Vec::new();
-        
-        let options = g.borrow().possible_moves(p);
-        for mv in options.into_iter() {
-            let score = g.borrow_mut().make_move(p, Rc::clone(&mv));
-                        
-            match score {
-                Ok(score) => match score {
-                    Score::Won(in_n) => {
-                        //println!("{}", &g.borrow().display());
-                        //println!("{:?} wins with {:?} in {}", p, mv.display(), in_n);
-                        g.borrow_mut().withdraw_move(p, Rc::clone(&mv));
-                        return (Some(mv), Some(Score::Won(in_n)));
-                    },
-                    Score::Remis(in_n) => { remis_option = Some((Rc::clone(&mv), in_n)); },
-                    Score::Lost(in_n) => { lost_options.push((Rc::clone(&mv), in_n)); },
-                    Score::Undecided(pv) => { undecided_options.push((Rc::clone(&mv), pv)); },
+        ) -> Vec<(T, Score)> where T: Clone {
+        let g = permissive(g);
+        let mut scores = Vec::new();
+        let options = g.read().unwrap().possible_moves(p);
+        for mv in options.into_iter().filter(|mv| mv.kind() == MoveKind::Drop) {
+            let score = g.write().unwrap().make_move(p, Arc::clone(&mv));
+            let score = match score {
+                Ok(Score::Undecided(pv)) if moves_ahead > 0 => {
+                    match self.find_best_move(Arc::clone(&g), p.opponent(), moves_ahead-1, false) {
+                        (_, Some(advscore)) => advscore.reverse(),
+                        (_, None) => Score::Undecided(pv),
+                    }
                 },
-                Err(_) => (),//return Err(_),
-            }
-            g.borrow_mut().withdraw_move(p, Rc::clone(&mv));
+                Ok(score) => score,
+                Err(_) => continue,
+            };
+            g.write().unwrap().withdraw_move(p, Arc::clone(&mv)).unwrap();
+            // moves that remain undecided are evaluated in the end, just like in find_best_move
+            let score = match score {
+                Score::Undecided(pv) if game_evaluation => {
+                    Score::Undecided(self.evaluate_move(Arc::clone(&g), p, Arc::clone(&mv)).unwrap_or(pv))
+                },
+                score => score,
+            };
+            scores.push((mv.data().clone(), score));
         }
-        
-        let mut still_undecided: Vec<(Rc<dyn Move<T>>, f32)> = Vec::new();
-        for (undecided, pv) in undecided_options {
-            if moves_ahead > 0 {
-                let _ = g.borrow_mut().make_move(p, Rc::clone(&undecided));
-                let (_, advscore) = self.find_best_move(Rc::clone(&g), p.opponent(), moves_ahead-1, false);
-                match advscore {
-                    Some(Score::Won(in_n)) => { lost_options.push((Rc::clone(&undecided), in_n+1)); },
-                    Some(Score::Remis(in_n)) => { remis_option = Some((Rc::clone(&undecided), in_n+1)); },
-                    Some(Score::Lost(in_n)) => { 
-                        g.borrow_mut().withdraw_move(p, Rc::clone(&undecided));
-                        return (Some(undecided), Some(Score::Won(in_n+1)));
-                    },
-                    Some(Score::Undecided(advpv)) => {
-                        still_undecided.push((Rc::clone(&undecided), 1.0-advpv));
-                    },
-                    None => println!("why is here None?"),
-                }
-                g.borrow_mut().withdraw_move(p, Rc::clone(&undecided));
-            } else {
-                still_undecided.push((Rc::clone(&undecided), pv));
+        scores
+    }
+
+    // the scores of analyze_position and the best moves of both players
+    // as far as the search looks ahead
+    fn analyze(&self,
+            g: Arc<RwLock<dyn Game<T,S>>>,
+            p: &Player,
+            moves_ahead: i32,
+            game_evaluation: bool
+        ) -> Analysis<T> where T: Clone {
+        let g = permissive(g);
+        let scores = self.analyze_position(Arc::clone(&g), p, moves_ahead, game_evaluation);
+        let variation = principal_variation(self, g, p, moves_ahead, game_evaluation, true);
+        Analysis { scores, variation }
+    }
+}
+
+impl Score {
+    // the score of the preceding move, as seen by the opponent
+    pub fn reverse(self) -> Score {
+        match self {
+            Score::Won(in_n) => Score::Lost(in_n+1),
+            Score::Lost(in_n) => Score::Won(in_n+1),
+            Score::Remis(in_n) => Score::Remis(in_n+1),
+            Score::Undecided(pv) => Score::Undecided(1.0-pv),
+        }
+    }
+}
+
+// the game itself if it is permissive, otherwise a permissive copy of it, if it can be copied.
+// strategies search on it, a game played by the rules would refuse half of the moves they try
+pub fn permissive<T,S>(g: Arc<RwLock<dyn Game<T,S>>>) -> Arc<RwLock<dyn Game<T,S>>> {
+    let copy = if g.read().unwrap().is_permissive() { None } else { g.read().unwrap().replicate() };
+    copy.unwrap_or(g)
+}
+
+// plays the best moves of both players from the given position on, until the game is over.
+// if look_ahead_shrinks, every following move is searched one move less ahead and the
+// variation ends where the search would not look any further. it ends before a move
+// that is not a drop as well. the game is left as it was.
+pub fn principal_variation<T,S,X>(st: &X,
+        g: Arc<RwLock<dyn Game<T,S>>>,
+        p: &Player,
+        moves_ahead: i32,
+        game_evaluation: bool,
+        look_ahead_shrinks: bool,
+    ) -> Vec<T>
+    where X: Strategy<T,S> + ?Sized, T: Clone {
+    let mut played: Vec<(Player, Arc<dyn Move<T>>)> = Vec::new();
+    let mut player = p.clone();
+    let mut depth = moves_ahead;
+    while depth >= 0 {
+        let mv = match st.find_best_move(Arc::clone(&g), &player, depth, game_evaluation) {
+            (Some(mv), _) if mv.kind() == MoveKind::Drop => mv,
+            _ => break,
+        };
+        let score = g.write().unwrap().make_move(&player, Arc::clone(&mv));
+        match score {
+            Ok(Score::Undecided(_)) => { played.push((player.clone(), mv)); },
+            // game over
+            Ok(_) => { played.push((player.clone(), mv)); break; },
+            Err(_) => break,
+        }
+        player = player.opponent().clone();
+        if look_ahead_shrinks { depth -= 1; }
+    }
+    for (player, mv) in played.iter().rev() {
+        g.write().unwrap().withdraw_move(player, Arc::clone(mv)).unwrap();
+    }
+    played.into_iter().map(|(_, mv)| mv.data().clone()).collect()
+}
+
+// the search of the default Strategy::find_best_move, for strategies that fall back on it
+pub fn search_best_move<T,S,X>(st: &X,
+        g: Arc<RwLock<dyn Game<T,S>>>,
+        p: &Player,
+        moves_ahead: i32,
+        game_evaluation: bool,
+    ) -> (Option<Arc<dyn Move<T>>>, Option<Score>)
+    where X: Strategy<T,S> + ?Sized {
+    // without a deadline the search always completes
+    look_ahead(st, g, p, moves_ahead, game_evaluation, None, st.threads()).unwrap()
+}
+
+// the iterative deepening of the default Strategy::find_best_move_in_time
+pub fn search_best_move_in_time<T,S,X>(st: &X,
+        g: Arc<RwLock<dyn Game<T,S>>>,
+        p: &Player,
+        budget: Duration,
+        game_evaluation: bool,
+    ) -> (Option<Arc<dyn Move<T>>>, Option<Score>)
+    where X: Strategy<T,S> + ?Sized {
+    let deadline = Instant::now() + budget;
+    let g = permissive(g);
+    // looking further ahead does not make a known move any better
+    let known = st.known_move(&*g.read().unwrap(), p);
+    if let Some((mv, score)) = known {
+        return (Some(mv), Some(score));
+    }
+    // not looking ahead at all is quick, there must be some move to return
+    let mut best = look_ahead(st, Arc::clone(&g), p, 0, game_evaluation, None, 1).unwrap();
+    let moves_left = g.read().unwrap().moves_left();
+    let mut moves_ahead = 1;
+    loop {
+        // a decision will not change with looking further ahead, nor will there be a move if there is none
+        match best.1 {
+            Some(Score::Won(_)) | Some(Score::Lost(_)) | Some(Score::Remis(_)) | None => return best,
+            _ => (),
+        }
+        // the last search went on to the end of the game already
+        if moves_left.is_some_and(|left| moves_ahead as usize >= left) {
+            return best;
+        }
+        match look_ahead(st, Arc::clone(&g), p, moves_ahead, game_evaluation, Some(deadline), st.threads()) {
+            Some(result) => { best = result; },
+            None => return best,
+        }
+        moves_ahead += 1;
+    }
+}
+
+// the search behind Strategy::find_best_move.
+// gives up with None once the deadline has passed, leaving the game as it was.
+// with more than one thread, the replies to the undecided moves are searched side by side,
+// the outcome is the same as searching them one after the other.
+#[allow(clippy::type_complexity)]
+fn look_ahead<T,S,X>(st: &X,
+        g: Arc<RwLock<dyn Game<T,S>>>,
+        p: &Player,
+        moves_ahead: i32,
+        game_evaluation: bool,
+        deadline: Option<Instant>,
+        threads: usize,
+    ) -> Option<(Option<Arc<dyn Move<T>>>, Option<Score>)>
+    where X: Strategy<T,S> + ?Sized {
+
+    if let Some(deadline) = deadline {
+        if Instant::now() >= deadline { return None; }
+    }
+    let known = st.known_move(&*g.read().unwrap(), p);
+    if let Some((mv, score)) = known {
+        return Some((Some(mv), Some(score)));
+    }
+
+    //let mut win_option: Option<Arc<Move<T>>> = None;
+    let mut remis_option: Option<(Arc<dyn Move<T>>,u32)> = None;
+    let mut lost_options: Vec<(Arc<dyn Move<T>>,u32)> = Vec::new();
+    let mut undecided_options: Vec<(Arc<dyn Move<T>>, f32)> = Vec::new();
+    
+    let options = g.read().unwrap().possible_moves(p);
+    for mv in options.into_iter() {
+        let score = g.write().unwrap().make_move(p, Arc::clone(&mv));
+                    
+        match score {
+            Ok(score) => match score {
+                Score::Won(in_n) => {
+                    //println!("{}", &g.read().unwrap().display());
+                    //println!("{:?} wins with {:?} in {}", p, mv.display(), in_n);
+                    g.write().unwrap().withdraw_move(p, Arc::clone(&mv)).unwrap();
+                    return Some((Some(mv), Some(Score::Won(in_n))));
+                },
+                Score::Remis(in_n) => { remis_option = Some((Arc::clone(&mv), in_n)); },
+                Score::Lost(in_n) => { lost_options.push((Arc::clone(&mv), in_n)); },
+                Score::Undecided(pv) => { undecided_options.push((Arc::clone(&mv), pv)); },
+            },
+            Err(_) => (),//return Err(_),
+        }
+        g.write().unwrap().withdraw_move(p, Arc::clone(&mv)).unwrap();
+    }
+    
+    let replies = if threads > 1 && moves_ahead > 0 {
+        search_replies(st, &g, p, &undecided_options, moves_ahead-1, deadline, threads)
+    } else {
+        None
+    };
+
+    let mut still_undecided: Vec<(Arc<dyn Move<T>>, f32)> = Vec::new();
+    for (i, (undecided, pv)) in undecided_options.into_iter().enumerate() {
+        if moves_ahead > 0 {
+            let reply = match replies {
+                Some(ref replies) => replies[i].clone(),
+                None => {
+                    let _ = g.write().unwrap().make_move(p, Arc::clone(&undecided));
+                    let reply = look_ahead(st, Arc::clone(&g), p.opponent(), moves_ahead-1, false, deadline, 1);
+                    g.write().unwrap().withdraw_move(p, Arc::clone(&undecided)).unwrap();
+                    reply.map(|(_, advscore)| advscore)
+                },
+            };
+            // the deadline has passed
+            match reply? {
+                Some(Score::Won(in_n)) => { lost_options.push((Arc::clone(&undecided), in_n+1)); },
+                Some(Score::Remis(in_n)) => { remis_option = Some((Arc::clone(&undecided), in_n+1)); },
+                Some(Score::Lost(in_n)) => { 
+                    return Some((Some(undecided), Some(Score::Won(in_n+1))));
+                },
+                Some(Score::Undecided(advpv)) => {
+                    still_undecided.push((Arc::clone(&undecided), 1.0-advpv));
+                },
+                None => println!("why is here None?"),
             }
+        } else {
+            still_undecided.push((Arc::clone(&undecided), pv));
         }
+    }
 
-        let mut undecided_option: Option<Rc<dyn Move<T>>> = None;
-        let mut undecided_pv = std::f32::MIN;
-        for (undecided, pv) in still_undecided {
-            if game_evaluation {
-                match self.evaluate_move(Rc::clone(&g), p, Rc::clone(&undecided)) {
-                    Ok(ev) => if ev > undecided_pv {
-                        undecided_option = Some(undecided);
-                        undecided_pv = ev;
-                    },
-                    Err(e) => println!("what's wrong with {:?}: {:?}", undecided.display(), e),
-                }
-                
-            } else {
-                if pv > undecided_pv {
+    let mut undecided_option: Option<Arc<dyn Move<T>>> = None;
+    let mut undecided_pv = std::f32::MIN;
+    for (undecided, pv) in still_undecided {
+        if game_evaluation {
+            match st.evaluate_move(Arc::clone(&g), p, Arc::clone(&undecided)) {
+                Ok(ev) => if ev > undecided_pv {
                     undecided_option = Some(undecided);
-                    undecided_pv = pv;
-                }
+                    undecided_pv = ev;
+                },
+                Err(e) => println!("what's wrong with {:?}: {:?}", undecided.display(), e),
+            }
+            
+        } else {
+            if pv > undecided_pv {
+                undecided_option = Some(undecided);
+                undecided_pv = pv;
             }
         }
+    }
 
-        if let Some(undecided) = undecided_option {
-            if let Some((remis, in_n)) = remis_option {
-                if undecided_pv >= 0.5 { return (Some(undecided), Some(Score::Undecided(undecided_pv))); }
-                else { return (Some(remis), Some(Score::Remis(in_n+1))); }
-            } else {
-                return (Some(undecided), Some(Score::Undecided(undecided_pv)));
-            }
+    if let Some(undecided) = undecided_option {
+        if let Some((remis, in_n)) = remis_option {
+            if undecided_pv >= 0.5 { return Some((Some(undecided), Some(Score::Undecided(undecided_pv)))); }
+            else { return Some((Some(remis), Some(Score::Remis(in_n+1)))); }
+        } else {
+            return Some((Some(undecided), Some(Score::Undecided(undecided_pv))));
         }
+    }
 
-        if let Some((remis, in_n)) = remis_option { return (Some(remis), Some(Score::Remis(in_n))); }
-        let mut latest_possible = None;
-        let mut latest = 0;
-        for (lost, in_n) in lost_options {
-            if in_n > latest { latest_possible = Some((lost, in_n)); latest = in_n; }
-            else {
-                match latest_possible {
-                     None => latest_possible = Some((lost, in_n)), _ => ()
-                }
+    if let Some((remis, in_n)) = remis_option { return Some((Some(remis), Some(Score::Remis(in_n)))); }
+    let mut latest_possible = None;
+    let mut latest = 0;
+    for (lost, in_n) in lost_options {
+        if in_n > latest { latest_possible = Some((lost, in_n)); latest = in_n; }
+        else {
+            match latest_possible {
+                 None => latest_possible = Some((lost, in_n)), _ => ()
             }
         }
-        if let Some((lost, in_n)) = latest_possible { return (Some(lost), Some(Score::Lost(in_n))); }
-//5: not clear what to do about this...
-/*5*/panic!("what?");
-//5:
-//:5        (None, None)
     }
+    if let Some((lost, in_n)) = latest_possible { return Some((Some(lost), Some(Score::Lost(in_n)))); }
+    // no move is possible, the field is full
+    Some((None, None))
 }
 
+// searches the opponent's best reply to each of the moves, the moves spread over the threads,
+// every thread with a copy of the game of its own. the replies come in the order of the moves,
+// None for a reply means the deadline has passed. None if the game cannot be copied.
+#[allow(clippy::type_complexity)]
+fn search_replies<T,S,X>(st: &X,
+        g: &Arc<RwLock<dyn Game<T,S>>>,
+        p: &Player,
+        moves: &[(Arc<dyn Move<T>>, f32)],
+        moves_ahead: i32,
+        deadline: Option<Instant>,
+        threads: usize,
+    ) -> Option<Vec<Option<Option<Score>>>>
+    where X: Strategy<T,S> + ?Sized {
+    let mut copies = Vec::new();
+    while copies.len() < threads && copies.len() < moves.len() {
+        copies.push(g.read().unwrap().replicate()?);
+    }
+    let n = copies.len();
+    if n < 2 {
+        return None;
+    }
+
+    let mut replies = vec![None; moves.len()];
+    thread::scope(|scope| {
+        let handles: Vec<_> = copies.into_iter().enumerate().map(|(t, copy)| {
+            scope.spawn(move || {
+                moves.iter().enumerate().skip(t).step_by(n).map(|(i, (mv, _))| {
+                    let _ = copy.write().unwrap().make_move(p, Arc::clone(mv));
+                    let reply = look_ahead(st, Arc::clone(&copy), p.opponent(), moves_ahead, false, deadline, 1);
+                    copy.write().unwrap().withdraw_move(p, Arc::clone(mv)).unwrap();
+                    (i, reply.map(|(_, advscore)| advscore))
+                }).collect::<Vec<_>>()
+            })
+        }).collect();
+        for handle in handles {
+            for (i, reply) in handle.join().unwrap() {
+                replies[i] = reply;
+            }
+        }
+    });
+    Some(replies)
+}
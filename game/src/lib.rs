@@ -1,3 +1,9 @@
 pub mod generic;
 pub mod connectfour;
-pub mod bruteforce;
\ No newline at end of file
+pub mod bruteforce;
+pub mod position;
+pub mod book;
+pub mod endgame;
+pub mod bitboard;
+pub mod solver;
+pub mod mcts;
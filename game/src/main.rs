@@ -1,26 +1,58 @@
 extern crate game;
-use game::bruteforce::{BruteForceStrategy,LIMIT,BASICALLY_OVER};
+use game::bruteforce::{BruteForceConfig,BruteForceStrategy,CancellationToken,Progress};
 use game::connectfour::*;
 use game::generic::*;
+use game::position::PositionKey;
+use game::book::OpeningBook;
+use game::endgame::EndgameTable;
 
 
-use std::time::{Instant};
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::time::{Duration,Instant};
+use std::sync::{Arc,RwLock};
+use std::thread;
 use std::env;
+use std::path::Path;
 
-fn time_pondering(game:&ConnectFour, nworker:usize, moves_ahead:i32, player:&Player) -> u64 {
-    let g = Rc::new(RefCell::new(game.clone()));
+fn time_pondering(game:&ConnectFour, config:&BruteForceConfig, moves_ahead:i32, player:&Player, store:Option<String>,
+                  deadline:Option<Duration>) -> u64 {
+    let g = Arc::new(RwLock::new(game.clone()));
 
     let then = Instant::now();
 
-    let result = match nworker {
-        0 => ConnectFourStrategy::default().find_best_move(g.clone(), player, moves_ahead, true),
-        n => BruteForceStrategy::new(n).find_best_move(g.clone(), player, moves_ahead, true),    
+    let result = match config.nworkers {
+        0 => {
+            let strategy = ConnectFourStrategy { endgame: config.endgame.clone(), ..ConnectFourStrategy::default() };
+            strategy.find_best_move(g.clone(), player, moves_ahead, true)
+        },
+        _ => {
+            let mut strategy = BruteForceStrategy::new(config.clone());
+            strategy.store = store;
+            strategy.progress = Some(Arc::new(|progress:&Progress| {
+                println!("{}\t{}\t{}", progress.interests, progress.records, progress.jobs);
+            }));
+            if let Some(deadline) = deadline {
+                let cancellation = CancellationToken::new();
+                strategy.cancellation = Some(cancellation.clone());
+                thread::spawn(move|| {
+                    thread::sleep(deadline);
+                    cancellation.cancel();
+                });
+            }
+            strategy.find_best_move(g.clone(), player, moves_ahead, true)
+        },
     };
     match result {
         (Some(mv), Some(score)) => {
             println!("{:?} {:?}", mv.data(), score);
+            // show the connected stones if the move wins right away
+            let mut played = game.clone();
+            if let Ok(Score::Won(_)) = played.drop_stone(player, mv.data().clone()) {
+                let cells:Vec<(usize,usize)> = played.winning_lines(mv.data()).into_iter().flatten().collect();
+                println!("{}", played.display_highlighted(&cells));
+                if let Some(moves) = played.move_string() {
+                    println!("moves {}", moves);
+                }
+            }
         },
         _ => (),
     }
@@ -31,29 +63,6 @@ fn time_pondering(game:&ConnectFour, nworker:usize, moves_ahead:i32, player:&Pla
 
 }
 
-fn game_from_hash(hash:i128) -> ConnectFour {
-    let mut game = ConnectFour::new();
-    let mut h = hash;
-    let base:i128 = 4;
-    for ci in 0..ConnectFour::width() {
-        let col = Column::from_usize(ci);
-        let mut cr = h % base.pow(ConnectFour::height() as u32);
-        for _ri in 0..ConnectFour::height() {
-            let stone = cr % base;
-            match stone {
-                1 => { game.drop_stone(&Player::White, col.clone()).unwrap(); },
-                2 => { game.drop_stone(&Player::Black, col.clone()).unwrap(); },
-                3 => { game.drop_stone(&Player::Gray, col.clone()).unwrap(); },
-                0 => break,
-                _ => (),
-            }
-            cr = cr / base;
-        }
-        h = h / base.pow(ConnectFour::height() as u32);
-    }
-    game
-}
-
 fn default_int(a:Option<&String>, default:usize) -> usize {
     match a {
         Some(n) => match n.parse::<usize>() {
@@ -64,7 +73,23 @@ fn default_int(a:Option<&String>, default:usize) -> usize {
     }
 }
 
-fn read_game_from_file(a:Option<&String>) -> ConnectFour {
+// the board a position argument starts with, "9x7:" or "8x7x5:" for width, height and win length,
+// and the rest of the argument. the standard board if there is none
+fn split_rules(a:&str) -> ((usize,usize,usize), Option<usize>, &str) {
+    let standard = (STANDARD_WIDTH, STANDARD_HEIGHT, STANDARD_WIN_LENGTH);
+    if let Some((board, rest)) = a.split_once(':') {
+        let numbers:Vec<Option<usize>> = board.split('x').map(|n| n.parse::<usize>().ok()).collect();
+        match numbers.as_slice() {
+            [Some(width), Some(height)] => return ((*width, *height, STANDARD_WIN_LENGTH), Some(*width), rest),
+            [Some(width), Some(height), Some(win_length)] => return ((*width, *height, *win_length), Some(*width), rest),
+            _ => (),
+        }
+    }
+    (standard, None, a)
+}
+
+// the game and the player to move, if the position tells
+fn read_game_from_file(a:Option<&String>) -> (ConnectFour, Option<Player>) {
     let default = "------
 
 x
@@ -74,15 +99,44 @@ xo
 o
 
 ------";
+    let (rules, width, a) = match a {
+        Some(a) => {
+            let (rules, width, rest) = split_rules(a);
+            (rules, width, Some(rest))
+        },
+        None => ((STANDARD_WIDTH, STANDARD_HEIGHT, STANDARD_WIN_LENGTH), None, None),
+    };
+    let (_, height, win_length) = rules;
     let plan = match a {
+        // moves:4453 plays the moves from an empty board
+        Some(moves) if moves.starts_with("moves:") => {
+            let game = ConnectFour::with_rules(rules.0, height, win_length).and_then(|mut game| {
+                game.play_move_string(&moves["moves:".len()..])?;
+                Ok(game)
+            });
+            match game {
+                Ok(game) => {
+                    let next = game.next_player().cloned();
+                    return (game, next);
+                },
+                Err(e) => panic!("{} is not a game: {}", moves, e),
+            }
+        },
         Some(path) => match std::fs::read_to_string(path) {
-            Err(_) =>  {
+            // a position in a single line, "/ox/x/o/// x" for instance, unless there is such a file
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound && path.contains('/') => String::from(path),
+            // only what is not a file at all may be a hash
+            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
                 let h = match path.parse::<i128>() {
                     Ok(u) => u,
                     Err(_) => panic!("{} neither file nor hash.", path),
                 };
-                return game_from_hash(h);
+                match PositionKey::decode(h, rules) {
+                    Ok(game) => return (game, None),
+                    Err(e) => panic!("{} is no game: {}", path, e),
+                }
             },
+            Err(e) => panic!("cannot read {}: {}", path, e),
             Ok(string) => string,
         },
         None =>  {
@@ -90,19 +144,87 @@ o
             String::from(default)
         },
     };
-    ConnectFour::replicate_game(&plan[..])
+    match ConnectFour::parse_position_with_rules(&plan[..], height, win_length) {
+        Ok((game, _)) if width.is_some_and(|width| width != game.width()) => {
+            panic!("{} has {} columns, not {}", a.unwrap_or("default"), game.width(), width.unwrap());
+        },
+        Ok((game, player)) => (game, Some(player)),
+        Err(e) => panic!("{} is not a game: {}", a.unwrap_or("default"), e),
+    }
+}
+
+// solves the positions of the first plies of the standard game and writes them to the book file
+fn write_book(path:&str, plies:usize, nworker:usize) {
+    let strategy = BruteForceStrategy::new(BruteForceConfig { nworkers: nworker, dump: None, ..BruteForceConfig::default() });
+    let rules = (STANDARD_WIDTH, STANDARD_HEIGHT, STANDARD_WIN_LENGTH);
+    let book = match OpeningBook::generate(rules, plies, &strategy, 0) {
+        Ok(book) => book,
+        Err(e) => panic!("no book: {}", e),
+    };
+    if let Err(e) = book.write(Path::new(path)) {
+        panic!("cannot write {}: {}", path, e);
+    }
+    println!("{} positions in {}", book.len(), path);
+}
+
+// solves every position the game may come to with at least so many stones and writes them to the table file
+fn write_endgame_table(path:&str, game:&ConnectFour, player:&Player, min_stones:usize) {
+    let table = match EndgameTable::generate_from(game, player, min_stones) {
+        Ok(table) => table,
+        Err(e) => panic!("no endgame table: {}", e),
+    };
+    if let Err(e) = table.write(Path::new(path)) {
+        panic!("cannot write {}: {}", path, e);
+    }
+    println!("{} positions in {}", table.len(), path);
+}
+
+// the table of the file, for the rules of the game
+fn read_endgame_table(path:&str, game:&ConnectFour) -> Arc<EndgameTable> {
+    let (width, height) = game.dimensions();
+    match EndgameTable::read(Path::new(path), (width, height, game.win_length())) {
+        Ok(table) => Arc::new(table),
+        Err(e) => panic!("cannot read {}: {}", path, e),
+    }
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
+    // book <file> [plies] [workers]
+    if args.get(1).map(|a| &a[..]) == Some("book") {
+        match args.get(2) {
+            Some(path) => write_book(path, default_int(args.get(3), 4), default_int(args.get(4), 3)),
+            None => panic!("book needs a file to write to."),
+        }
+        return;
+    }
+    // endgame <file> <position> [stones], every position after the given one by default.
+    // the earlier the position, the longer it takes, a dozen empty cells are done in no time
+    if args.get(1).map(|a| &a[..]) == Some("endgame") {
+        match (args.get(2), args.get(3)) {
+            (Some(path), Some(_)) => {
+                let (game, to_move) = read_game_from_file(args.get(3));
+                let min_stones = default_int(args.get(4), game.dropped_stones());
+                write_endgame_table(path, &game, &to_move.unwrap_or(Player::White), min_stones);
+            },
+            _ => panic!("endgame needs a file to write to and a position to start from."),
+        }
+        return;
+    }
+
     let nworker = default_int(args.get(3), 3);
     let moves_ahead = default_int(args.get(4), 4) as i32;
-    let game = read_game_from_file(args.get(1));
-    unsafe {
-        BASICALLY_OVER = default_int(args.get(5), 30) as usize;
-        LIMIT = default_int(args.get(6), 0) as u128;
-    }
+    let (game, to_move) = read_game_from_file(args.get(1));
+    // the positions of the endgame table are looked up by either strategy
+    let endgame = args.get(9).map(|path| read_endgame_table(path, &game));
+    let config = BruteForceConfig {
+        nworkers: nworker,
+        basically_over: default_int(args.get(5), 30),
+        time_limit: Duration::from_millis(default_int(args.get(6), 0) as u64),
+        endgame,
+        ..BruteForceConfig::default()
+    };
     let player = match &args.get(2) {
         Some(p) =>  {
             match &p[..] {
@@ -112,13 +234,15 @@ fn main() {
                 _ => panic!("{} neither black nor white. you are black then.", p),
             }
         },
-        None => Player::Black,
+        None => to_move.unwrap_or(Player::Black),
     };
     
+    let deadline = args.get(8).map(|_| Duration::from_secs(default_int(args.get(8), 0) as u64));
+
     let games = [game,];
     let _timep = games.iter()
     .map(|game| {
-        time_pondering(game, nworker, moves_ahead, &player)
+        time_pondering(game, &config, moves_ahead, &player, args.get(7).cloned(), deadline)
     })
     .map(|tp| {
         println!("ran with {} workers, it took {} seconds", nworker, tp);
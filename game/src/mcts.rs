@@ -0,0 +1,326 @@
+//pub mod generic;
+use generic::{Game,GameError,Move,MoveKind,Player,Score,Strategy};
+use connectfour::{Column,ConnectFourStrategy};
+use std::sync::{Arc,RwLock};
+use std::time::{Duration,Instant};
+
+//#################################################################################################
+// Monte Carlo tree search
+//#################################################################################################
+
+#[derive(Debug, Clone)]
+pub struct MctsConfig {
+    // games played to the end per search
+    pub playouts: usize,
+    // a search stops early once it took that long
+    pub time_limit: Option<Duration>,
+    // how much UCT favours moves that were tried less often over the ones that won more often
+    pub exploration: f32,
+    // the moves of a playout are the ones ConnectFourStrategy rates best rather than random ones.
+    // the playouts get stronger, but far fewer of them fit into the same time
+    pub heuristic_playouts: bool,
+    // the same seed makes the same search
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            playouts: 10000,
+            time_limit: None,
+            exploration: std::f32::consts::SQRT_2,
+            heuristic_playouts: false,
+            seed: 4453,
+        }
+    }
+}
+
+// finds its moves by playing many games to the end and going for the ones that were won most often.
+// it knows nothing about Connect Four but the rules, unless the playouts are heuristic
+pub struct MctsStrategy {
+    pub config: MctsConfig,
+    // rates the moves of heuristic playouts
+    pub heuristic: ConnectFourStrategy,
+}
+
+impl MctsStrategy {
+    pub fn new(config: MctsConfig) -> Self {
+        MctsStrategy { config, heuristic: ConnectFourStrategy::default() }
+    }
+}
+
+// the position after a move
+struct Node {
+    // the player who moved and the move, none for the root
+    mv: Option<(Player, Arc<dyn Move<Column>>)>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    // the moves that have no node yet
+    untried: Vec<Arc<dyn Move<Column>>>,
+    visits: u32,
+    // the playouts through the node the mover won, draws count half
+    wins: f32,
+    // the game was over with the move, won by the player or drawn
+    result: Option<Option<Player>>,
+}
+
+impl Node {
+    fn win_rate(&self) -> f32 {
+        if self.visits == 0 { 0.5 } else { self.wins / self.visits as f32 }
+    }
+}
+
+// xorshift, there is no need for better random numbers
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
+
+// the winner of a move that ended the game, None for a draw, or nothing if the game goes on
+fn result(score: &Score, p: &Player) -> Option<Option<Player>> {
+    match score {
+        Score::Won(_) => Some(Some(p.clone())),
+        Score::Lost(_) => Some(Some(p.opponent().clone())),
+        Score::Remis(_) => Some(None),
+        Score::Undecided(_) => None,
+    }
+}
+
+impl MctsStrategy {
+    // the tree of a search from the position, the root first. the game is left as it was
+    #[allow(clippy::type_complexity)]
+    fn search(&self, g: &Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>, p: &Player, deadline: Option<Instant>)
+    -> Vec<Node> {
+        // the heuristic locks the game itself, a copy keeps other threads from waiting
+        let replica = g.read().unwrap().replicate();
+        let game = replica.as_ref().unwrap_or(g);
+        let mut random = Random(self.config.seed | 1);
+        let mut tree = vec![Node {
+            mv: None, parent: None, children: Vec::new(),
+            untried: game.read().unwrap().possible_moves(p),
+            visits: 0, wins: 0.0, result: None,
+        }];
+        // a budget of the caller's replaces both limits of the configuration
+        let (deadline, playouts) = match deadline {
+            Some(deadline) => (Some(deadline), usize::MAX),
+            None => (self.config.time_limit.map(|limit| Instant::now() + limit), self.config.playouts),
+        };
+        let mut played = 0;
+        loop {
+            self.playout(game, p, &mut tree, &mut random);
+            played += 1;
+            if played >= playouts || deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+        }
+        tree
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn playout(&self, g: &Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>, root_player: &Player,
+               tree: &mut Vec<Node>, random: &mut Random) {
+        let mut played: Vec<(Player, Arc<dyn Move<Column>>)> = Vec::new();
+        let mut player = root_player.clone();
+
+        // selection: down the tree along the moves with the best upper confidence bound
+        let mut node = 0;
+        while tree[node].result.is_none() && tree[node].untried.is_empty() && !tree[node].children.is_empty() {
+            let ln_visits = (tree[node].visits.max(1) as f32).ln();
+            let uct = |child: &Node| -> f32 {
+                if child.visits == 0 { return f32::MAX; }
+                child.win_rate() + self.config.exploration * (ln_visits / child.visits as f32).sqrt()
+            };
+            node = *tree[node].children.iter()
+                .max_by(|a, b| uct(&tree[**a]).partial_cmp(&uct(&tree[**b])).unwrap())
+                .unwrap();
+            let (mover, mv) = tree[node].mv.clone().unwrap();
+            let _ = g.write().unwrap().make_move(&mover, Arc::clone(&mv));
+            played.push((mover, mv));
+            player = player.opponent().clone();
+        }
+
+        // expansion: a move that was not tried yet gets a node
+        if tree[node].result.is_none() && !tree[node].untried.is_empty() {
+            let i = random.below(tree[node].untried.len());
+            let mv = tree[node].untried.swap_remove(i);
+            let score = g.write().unwrap().make_move(&player, Arc::clone(&mv));
+            if let Ok(score) = score {
+                played.push((player.clone(), Arc::clone(&mv)));
+                let result = result(&score, &player);
+                player = player.opponent().clone();
+                let untried = if result.is_none() { g.read().unwrap().possible_moves(&player) } else { Vec::new() };
+                tree.push(Node {
+                    mv: Some((played.last().unwrap().0.clone(), mv)), parent: Some(node), children: Vec::new(),
+                    untried, visits: 0, wins: 0.0, result,
+                });
+                let child = tree.len() - 1;
+                tree[node].children.push(child);
+                node = child;
+            }
+        }
+
+        // simulation: the game is played to the end
+        // popping stones may go on forever, a playout that long counts as a draw
+        let (width, height) = g.read().unwrap().dimensions();
+        let mut moves_left = 4 * width * height;
+        let mut winner = tree[node].result.clone();
+        while winner.is_none() {
+            let options = g.read().unwrap().possible_moves(&player);
+            if options.is_empty() || moves_left == 0 {
+                winner = Some(None);
+                break;
+            }
+            moves_left -= 1;
+            let mv = if self.config.heuristic_playouts {
+                self.heuristic_choice(g, &player, options, random)
+            } else {
+                let i = random.below(options.len());
+                Arc::clone(&options[i])
+            };
+            let score = g.write().unwrap().make_move(&player, Arc::clone(&mv));
+            match score {
+                Ok(score) => {
+                    played.push((player.clone(), mv));
+                    winner = result(&score, &player);
+                },
+                // a game that is not permissive refuses the moves of the wrong player
+                Err(_) => winner = Some(None),
+            }
+            player = player.opponent().clone();
+        }
+        let winner = winner.unwrap();
+
+        // backpropagation: every node on the way counts the game for the player who moved into it
+        let mut up = Some(node);
+        while let Some(n) = up {
+            tree[n].visits += 1;
+            if let Some((mover, _)) = &tree[n].mv {
+                tree[n].wins += match &winner {
+                    Some(w) if w == mover => 1.0,
+                    Some(_) => 0.0,
+                    None => 0.5,
+                };
+            }
+            up = tree[n].parent;
+        }
+
+        for (p, mv) in played.into_iter().rev() {
+            g.write().unwrap().withdraw_move(&p, mv).unwrap();
+        }
+    }
+
+    // the move ConnectFourStrategy rates best, a random one of them if there are several.
+    // any of the options if it rates none of them
+    #[allow(clippy::type_complexity)]
+    fn heuristic_choice(&self, g: &Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>, p: &Player,
+                        options: Vec<Arc<dyn Move<Column>>>, random: &mut Random) -> Arc<dyn Move<Column>> {
+        let mut best: Vec<Arc<dyn Move<Column>>> = Vec::new();
+        let mut best_value = f32::MIN;
+        for mv in options.iter().cloned() {
+            let value = match self.heuristic.evaluate_move(Arc::clone(g), p, Arc::clone(&mv)) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            if value > best_value {
+                best_value = value;
+                best.clear();
+            }
+            if value >= best_value {
+                best.push(mv);
+            }
+        }
+        if best.is_empty() {
+            best = options;
+        }
+        let i = random.below(best.len());
+        Arc::clone(&best[i])
+    }
+
+    // what the search found out about a move from the root: a win if it wins at once, the win rate otherwise
+    fn score(node: &Node) -> Score {
+        match &node.result {
+            Some(Some(winner)) if node.mv.as_ref().is_some_and(|(mover, _)| mover == winner) => Score::Won(0),
+            Some(Some(_)) => Score::Lost(0),
+            Some(None) => Score::Remis(0),
+            None => Score::Undecided(node.win_rate()),
+        }
+    }
+
+    // a move that wins at once, otherwise the one played most often
+    fn best_child(tree: &[Node]) -> Option<&Node> {
+        let children = tree[0].children.iter().map(|child| &tree[*child]);
+        children.clone().find(|child| MctsStrategy::score(child) == Score::Won(0))
+            .or_else(|| children.max_by_key(|child| child.visits))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn best_move(&self, g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>, p: &Player, deadline: Option<Instant>)
+    -> (Option<Arc<dyn Move<Column>>>, Option<Score>) {
+        let tree = self.search(&g, p, deadline);
+        match MctsStrategy::best_child(&tree) {
+            Some(child) => (child.mv.as_ref().map(|(_, mv)| Arc::clone(mv)), Some(MctsStrategy::score(child))),
+            None => (None, None),
+        }
+    }
+}
+
+impl Strategy<Column,Vec<Vec<Option<Player>>>> for MctsStrategy {
+    // the win rate of the move in a search of the position
+    fn evaluate_move(&self, g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+                     p: &Player, mv: Arc<dyn Move<Column>>)
+    -> Result<f32, GameError> {
+        // a move that is not possible fails the way it would in the game
+        let score = g.write().unwrap().make_move(p, Arc::clone(&mv))?;
+        g.write().unwrap().withdraw_move(p, Arc::clone(&mv))?;
+        if let Score::Won(_) = score {
+            return Ok(1.0);
+        }
+        let tree = self.search(&g, p, None);
+        Ok(tree[0].children.iter().map(|child| &tree[*child])
+            .find(|child| child.mv.as_ref().is_some_and(|(_, m)| m.kind() == mv.kind() && m.data() == mv.data()))
+            .map(|child| child.win_rate())
+            .unwrap_or(0.5))
+    }
+
+    // the playouts go to the end of the game, however far ahead
+    fn find_best_move(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            _moves_ahead: i32,
+            _game_evaluation: bool
+        ) -> (Option<Arc<dyn Move<Column>>>, Option<Score>) {
+        self.best_move(g, p, None)
+    }
+
+    // as many playouts as fit into the budget
+    fn find_best_move_in_time(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            budget: Duration,
+            _game_evaluation: bool
+        ) -> (Option<Arc<dyn Move<Column>>>, Option<Score>) {
+        self.best_move(g, p, Some(Instant::now() + budget))
+    }
+
+    // the win rates of a single search, the drops in the order of Game::possible_moves
+    fn analyze_position(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            _moves_ahead: i32,
+            _game_evaluation: bool
+        ) -> Vec<(Column, Score)> {
+        let tree = self.search(&g, p, None);
+        let options = g.read().unwrap().possible_moves(p);
+        options.into_iter().filter(|mv| mv.kind() == MoveKind::Drop).filter_map(|mv| {
+            tree[0].children.iter().map(|child| &tree[*child])
+                .find(|child| child.mv.as_ref().is_some_and(|(_, m)| m.kind() == MoveKind::Drop && m.data() == mv.data()))
+                .map(|child| (mv.data().clone(), MctsStrategy::score(child)))
+        }).collect()
+    }
+}
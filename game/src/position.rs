@@ -0,0 +1,110 @@
+//pub mod generic;
+use generic::{Game,GameError,Player};
+use connectfour::{Column,ConnectFour};
+use bruteforce::{MAX_CELLS,hash_from_state};
+
+//#################################################################################################
+// keys of positions, for the game store and whatever else keeps positions
+//#################################################################################################
+
+// how gray stones count in a key
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Shading {
+    // a gray stone is a stone of its own, the key is exactly the field
+    Keep,
+    // every stone that can no longer be part of a line of its player's counts as gray,
+    // so positions that only differ in how much shading has caught up share a key
+    Ignore,
+}
+
+// the key a position and its mirror image share: the base 4 hash of the field taken from the side
+// bruteforce::hash_from_state takes it from, the one bias prefers.
+// every column takes height digits, the first column the lowest ones and each column bottom up:
+// 1 is a white stone, 2 a black one, 3 a gray one and 0 an empty cell, with nothing above it.
+// a key turns back into its position, PositionKey::of(&key.game(rules)?) is the key again, not mirrored
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub struct PositionKey(i128);
+
+impl PositionKey {
+    // the key of the game and whether it was taken from the other side of the board.
+    // None on a board of more than MAX_CELLS cells
+    pub fn of(game: &ConnectFour) -> Option<(PositionKey, bool)> {
+        game.hash().map(|(hash, mirrored)| (PositionKey(hash), mirrored))
+    }
+
+    // the same for a game that is only known to be some Connect Four, at the cost of going through its field
+    pub fn of_game(game: &dyn Game<Column,Vec<Vec<Option<Player>>>>) -> Option<(PositionKey, bool)> {
+        let (width, height) = game.dimensions();
+        if width * height > MAX_CELLS {
+            return None;
+        }
+        let (hash, mirrored) = hash_from_state(game.state(), height);
+        Some((PositionKey(hash), mirrored))
+    }
+
+    pub fn with_shading(game: &ConnectFour, shading: Shading) -> Option<(PositionKey, bool)> {
+        match shading {
+            Shading::Keep => PositionKey::of(game),
+            Shading::Ignore => {
+                let mut shaded = game.clone();
+                shaded.shade_dead_stones();
+                PositionKey::of(&shaded)
+            },
+        }
+    }
+
+    // a key that was kept as a number, if it is the key of a position on a board of the rules.
+    // the rules are width, height and win length of the board
+    pub fn from_hash(hash: i128, rules: (usize, usize, usize)) -> Result<PositionKey, GameError> {
+        let game = PositionKey::decode(hash, rules)?;
+        match PositionKey::of(&game) {
+            Some((key, false)) if key.0 == hash => Ok(key),
+            // the hash of the mirror image
+            _ => Err(GameError::InvalidKey(hash)),
+        }
+    }
+
+    pub fn hash(&self) -> i128 {
+        self.0
+    }
+
+    // the position of the key, a permissive game that knows nothing of who moved last
+    pub fn game(&self, rules: (usize, usize, usize)) -> Result<ConnectFour, GameError> {
+        PositionKey::decode(self.0, rules)
+    }
+
+    // the position of a hash taken from either side of the board, the mirror image is as good as the key.
+    // a hash with stones above an empty cell or more digits than cells is no position
+    pub fn decode(hash: i128, (width, height, win_length): (usize, usize, usize)) -> Result<ConnectFour, GameError> {
+        if width * height > MAX_CELLS {
+            return Err(GameError::InvalidDimensions(width, height));
+        }
+        let mut game = ConnectFour::with_rules(width, height, win_length)?;
+        if hash < 0 || hash >= 4i128.pow((width * height) as u32) {
+            return Err(GameError::InvalidKey(hash));
+        }
+        let mut h = hash;
+        for n in 0..width {
+            let column = Column::from_usize(n)?;
+            let mut ended = false;
+            for _m in 0..height {
+                let stone = match h % 4 {
+                    1 => Player::White,
+                    2 => Player::Black,
+                    3 => Player::Gray,
+                    _ => {
+                        ended = true;
+                        h /= 4;
+                        continue;
+                    },
+                };
+                if ended {
+                    return Err(GameError::InvalidKey(hash));
+                }
+                game.drop_stone(&stone, column.clone())?;
+                h /= 4;
+            }
+        }
+        Ok(game)
+    }
+}
@@ -0,0 +1,365 @@
+//pub mod generic;
+use generic::{Game,GameError,Move,Player,Score,Strategy};
+use connectfour::{Column,ConnectFourMove,STANDARD_WIN_LENGTH};
+use bitboard::{BitBoard,column_mask};
+use std::sync::{Arc,Mutex,MutexGuard,RwLock};
+use std::time::Duration;
+
+//#################################################################################################
+// exact Connect Four solver
+//#################################################################################################
+
+// a game that is won when the k-th stone is dropped is worth WIN-k to the winner
+// and k-WIN to the loser, so quick wins and late losses score best. draws are worth 0.
+const WIN: i32 = 100;
+
+#[derive(Clone, Copy)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    boards: [u64; 2],
+    player: bool,
+    value: i32,
+    bound: Bound,
+}
+
+// transposition table, a newer entry simply replaces an older one in the same slot
+struct Table {
+    entries: Vec<Option<Entry>>,
+    bits: u32,
+}
+
+impl Table {
+    fn new(bits: u32) -> Self {
+        Table {
+            entries: vec![None; 1 << bits],
+            bits,
+        }
+    }
+
+    // an empty table of the size, the old one is reused if it has that size already
+    fn reset(&mut self, bits: u32) {
+        if self.bits == bits {
+            for entry in self.entries.iter_mut() {
+                *entry = None;
+            }
+        } else {
+            *self = Table::new(bits);
+        }
+    }
+
+    fn slot(&self, boards: &[u64; 2], player: bool) -> usize {
+        let key = boards[0].wrapping_mul(0x9e37_79b9_7f4a_7c15)
+                ^ boards[1].rotate_left(29).wrapping_mul(0xc2b2_ae3d_27d4_eb4f)
+                ^ player as u64;
+        (key >> (64 - self.bits)) as usize
+    }
+
+    fn get(&self, bb: &BitBoard, p: &Player) -> Option<(i32, Bound)> {
+        let player = *p == Player::Black;
+        match self.entries[self.slot(bb.state(), player)] {
+            Some(entry) if entry.boards == *bb.state() && entry.player == player => {
+                Some((entry.value, entry.bound))
+            },
+            _ => None,
+        }
+    }
+
+    fn put(&mut self, bb: &BitBoard, p: &Player, value: i32, bound: Bound) {
+        let player = *p == Player::Black;
+        let slot = self.slot(bb.state(), player);
+        self.entries[slot] = Some(Entry { boards: *bb.state(), player, value, bound });
+    }
+}
+
+//### solver strategy #############################################################################
+
+pub struct SolverStrategy {
+    // the transposition table holds 2^table_bits positions
+    pub table_bits: u32,
+    // allocated once and cleared for every solve, the entries of the positions stay true in between
+    table: Mutex<Table>,
+}
+
+impl Default for SolverStrategy {
+    fn default() -> Self {
+        SolverStrategy::new(20)
+    }
+}
+
+impl SolverStrategy {
+    pub fn new(table_bits: u32) -> Self {
+        SolverStrategy { table_bits, table: Mutex::new(Table::new(table_bits)) }
+    }
+
+    // the table as the last search left it, a search in another thread has to finish first
+    fn table(&self) -> MutexGuard<'_, Table> {
+        self.table.lock().unwrap()
+    }
+
+    // the table for a new search
+    fn fresh_table(&self) -> MutexGuard<'_, Table> {
+        let mut table = self.table();
+        table.reset(self.table_bits);
+        table
+    }
+
+    // exact score of every possible move, in column order. none if the game is over
+    pub fn solve_moves(&self, bb: &BitBoard, p: &Player) -> Vec<(Column, Score)> {
+        let mut bb = *bb;
+        if is_over(&bb) {
+            return Vec::new();
+        }
+        let mut table = self.fresh_table();
+        let order = center_first();
+        let mut scores = Vec::new();
+        for n in 0..BitBoard::width() {
+            if bb.playable() & column_mask(n) == 0 { continue; }
+            let value = self.move_value(&mut bb, p, n, &mut table, &order);
+            scores.push((Column::from_usize(n).unwrap(), score_from_value(value, bb.dropped_stones())));
+        }
+        scores
+    }
+
+    // exact best move, the first one from the center outwards if there are several
+    pub fn solve(&self, bb: &BitBoard, p: &Player) -> Result<(Column, Score), GameError> {
+        let mut bb = *bb;
+        if is_over(&bb) {
+            return Err(GameError::GameOver);
+        }
+        let mut table = self.fresh_table();
+        let order = center_first();
+        let value = self.value(&mut bb, p, &mut table, &order);
+        // with the table filled, looking for the move that keeps the value is cheap
+        for n in order.iter() {
+            if bb.playable() & column_mask(*n) == 0 { continue; }
+            bb.play(p, *n);
+            let keeps = bb.connects_four(p)
+                || -self.negamax(&mut bb, p.opponent(), -value, 1 - value, &mut table, &order) >= value;
+            bb.unplay(*n);
+            if keeps {
+                return Ok((Column::from_usize(*n).unwrap(), score_from_value(value, bb.dropped_stones())));
+            }
+        }
+        // no position the players can have come to
+        Err(GameError::NoSolution)
+    }
+
+    fn move_value(&self, bb: &mut BitBoard, p: &Player, n: usize,
+                  table: &mut Table, order: &[usize]) -> i32 {
+        bb.play(p, n);
+        let value = if bb.connects_four(p) {
+            WIN - bb.dropped_stones() as i32
+        } else {
+            -self.value(bb, p.opponent(), table, order)
+        };
+        bb.unplay(n);
+        value
+    }
+
+    // exact value of the position for the player to move, narrowed down by null window searches.
+    // quick wins and losses are tried first, they are the cheap ones.
+    fn value(&self, bb: &mut BitBoard, p: &Player, table: &mut Table, order: &[usize]) -> i32 {
+        let stones = bb.dropped_stones() as i32;
+        let mut min = stones + 2 - WIN;
+        let mut max = WIN - (stones + 1);
+        while min < max {
+            let mut med = min + (max - min) / 2;
+            if med <= 0 && min / 2 < med {
+                med = min / 2;
+            } else if med >= 0 && max / 2 > med {
+                med = max / 2;
+            }
+            let value = self.negamax(bb, p, med, med + 1, table, order);
+            if value <= med {
+                max = value;
+            } else {
+                min = value;
+            }
+        }
+        min
+    }
+
+    fn negamax(&self, bb: &mut BitBoard, p: &Player, mut alpha: i32, mut beta: i32,
+               table: &mut Table, order: &[usize]) -> i32 {
+        let stones = bb.dropped_stones() as i32;
+        let playable = bb.playable();
+        // board is full
+        if playable == 0 {
+            return 0;
+        }
+        // win right away
+        if bb.winning_cells(p) & playable != 0 {
+            return WIN - (stones + 1);
+        }
+        // block the opponent if necessary and never play right below an opponent's winning cell
+        let threats = bb.winning_cells(p.opponent());
+        let mut candidates = playable;
+        let forced = playable & threats;
+        if forced != 0 {
+            if forced.count_ones() > 1 {
+                return stones + 2 - WIN;
+            }
+            candidates = forced;
+        }
+        candidates &= !(threats >> 1);
+        if candidates == 0 {
+            return stones + 2 - WIN;
+        }
+
+        // the opponent cannot win with the next stone, neither can we with this one
+        let min = stones + 4 - WIN;
+        let max = WIN - (stones + 3);
+        if alpha < min {
+            alpha = min;
+            if alpha >= beta { return alpha; }
+        }
+        if beta > max {
+            beta = max;
+            if alpha >= beta { return beta; }
+        }
+
+        if let Some((value, bound)) = table.get(bb, p) {
+            match bound {
+                Bound::Exact => return value,
+                Bound::Lower => if value > alpha { alpha = value; },
+                Bound::Upper => if value < beta { beta = value; },
+            }
+            if alpha >= beta { return value; }
+        }
+
+        // moves that leave the most winning cells behind first
+        let mut moves: Vec<(usize, u32)> = order.iter()
+            .filter(|n| { candidates & column_mask(**n) != 0 })
+            .map(|n| {
+                bb.play(p, *n);
+                let threats = bb.winning_cells(p).count_ones();
+                bb.unplay(*n);
+                (*n, threats)
+            })
+            .collect();
+        moves.sort_by(|a, b| { b.1.cmp(&a.1) });
+
+        let original_alpha = alpha;
+        for (n, _) in moves.into_iter() {
+            bb.play(p, n);
+            let value = -self.negamax(bb, p.opponent(), -beta, -alpha, table, order);
+            bb.unplay(n);
+            if value >= beta {
+                table.put(bb, p, value, Bound::Lower);
+                return value;
+            }
+            if value > alpha {
+                alpha = value;
+            }
+        }
+        table.put(bb, p, alpha, if alpha > original_alpha { Bound::Exact } else { Bound::Upper });
+        alpha
+    }
+}
+
+// a line is connected already or the board is full
+fn is_over(bb: &BitBoard) -> bool {
+    bb.playable() == 0 || bb.connects_four(&Player::White) || bb.connects_four(&Player::Black)
+}
+
+// columns sorted by their distance to the center
+fn center_first() -> Vec<usize> {
+    let w = BitBoard::width() as i32;
+    let mut order: Vec<usize> = (0..BitBoard::width()).collect();
+    order.sort_by_key(|n| { (2 * *n as i32 - (w - 1)).abs() });
+    order
+}
+
+// turn the value of a move into the score of the player making it
+// in_n counts the stones dropped after the move until the game is over
+fn score_from_value(value: i32, stones: usize) -> Score {
+    let stones = stones as i32;
+    if value > 0 {
+        Score::Won((WIN - value - stones - 1) as u32)
+    } else if value < 0 {
+        Score::Lost((WIN + value - stones - 1) as u32)
+    } else {
+        Score::Remis((BitBoard::width() * BitBoard::height()) as u32 - stones as u32 - 1)
+    }
+}
+
+type SharedGame = Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>;
+
+// the solver works on bitboards, it plays connect four on the standard board without Pop Out only
+fn bitboard(g: &SharedGame) -> Result<BitBoard, GameError> {
+    let game = g.read().unwrap();
+    if game.dimensions() != (BitBoard::width(), BitBoard::height())
+        || game.win_length() != STANDARD_WIN_LENGTH || game.pop_out() {
+        return Err(GameError::UnsupportedRules);
+    }
+    Ok(BitBoard::from_state(game.state()))
+}
+
+impl Strategy<Column,Vec<Vec<Option<Player>>>> for SolverStrategy {
+
+    // 1 for a winning move, 0.5 for a draw and 0 for a losing move
+    fn evaluate_move(&self, g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+                     p: &Player, mv: Arc<dyn Move<Column>>)
+    -> Result<f32, GameError> {
+        let mut bb = bitboard(&g)?;
+        if is_over(&bb) {
+            return Err(GameError::GameOver);
+        }
+        let n = mv.data().to_usize();
+        if n >= BitBoard::width() {
+            return Err(GameError::InvalidColumn(n));
+        }
+        if bb.playable() & column_mask(n) == 0 {
+            return Err(GameError::ColumnFull);
+        }
+        let mut table = self.table();
+        let value = self.move_value(&mut bb, p, n, &mut table, &center_first());
+        Ok(match value {
+            v if v > 0 => 1.0,
+            0 => 0.5,
+            _ => 0.0,
+        })
+    }
+
+    // exact scores, however far ahead
+    fn analyze_position(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            _moves_ahead: i32,
+            _game_evaluation: bool
+        ) -> Vec<(Column, Score)> {
+        match bitboard(&g) {
+            Ok(bb) => self.solve_moves(&bb, p),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    fn find_best_move(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            // the solver always searches to the end of the game
+            _moves_ahead: i32,
+            _game_evaluation: bool
+        ) -> (Option<Arc<dyn Move<Column>>>, Option<Score>) {
+        match bitboard(&g).and_then(|bb| self.solve(&bb, p)) {
+            Ok((column, score)) => (Some(Arc::new(ConnectFourMove { data: column })), Some(score)),
+            Err(_) => (None, None),
+        }
+    }
+
+    // there is nothing to deepen, the position is solved however long it takes
+    fn find_best_move_in_time(&self,
+            g: Arc<RwLock<dyn Game<Column,Vec<Vec<Option<Player>>>>>>,
+            p: &Player,
+            _budget: Duration,
+            game_evaluation: bool
+        ) -> (Option<Arc<dyn Move<Column>>>, Option<Score>) {
+        self.find_best_move(g, p, 0, game_evaluation)
+    }
+}
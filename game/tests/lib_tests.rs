@@ -2,9 +2,17 @@ extern crate game;
 use game::connectfour::*;
 use game::generic::*;
 use game::bruteforce::*;
-
-use std::rc::Rc;
-use std::cell::RefCell;
+use game::position::*;
+use game::book::*;
+use game::endgame::*;
+use game::bitboard::*;
+use game::solver::*;
+use game::mcts::*;
+
+use std::convert::TryFrom;
+use std::sync::{Arc,RwLock};
+use std::thread;
+use std::time::{Duration,Instant};
 
 const TOLERANCE:f32 = 0.0001;
 
@@ -23,7 +31,7 @@ fn test_move() {
     let mut cf = ConnectFour::new();
     for i in 0..7 {
         //println!("drop {} time", i+1);
-        let middle = Rc::new(ConnectFourMove { data: Column::Four });
+        let middle = Arc::new(ConnectFourMove { data: Column::Four });
         match cf.make_move(&white, middle) {
             Ok(x) => match x {
                 // should be undecided 3 times
@@ -42,41 +50,479 @@ fn test_move() {
 
     // drop 4 stones in a row
     let mut cf = ConnectFour::new();
-    match cf.make_move(&white, Rc::new(ConnectFourMove { data: Column::Four })) {
+    match cf.make_move(&white, Arc::new(ConnectFourMove { data: Column::Four })) {
         Ok(x) => if let Score::Undecided(_p) = x { () } else { assert!(false)},
         _ => assert!(false),
     }
-    match cf.make_move(&white, Rc::new(ConnectFourMove { data: Column::Two })) {
+    match cf.make_move(&white, Arc::new(ConnectFourMove { data: Column::Two })) {
         Ok(x) => if let Score::Undecided(_p) = x { () } else { assert!(false)},
         _ => assert!(false),
     }
-    match cf.make_move(&white, Rc::new(ConnectFourMove { data: Column::Five })) {
+    match cf.make_move(&white, Arc::new(ConnectFourMove { data: Column::Five })) {
         Ok(x) => if let Score::Undecided(_p) = x { () } else { assert!(false)},
         _ => assert!(false),
     }
-    match cf.make_move(&white, Rc::new(ConnectFourMove { data: Column::Three })) {
+    match cf.make_move(&white, Arc::new(ConnectFourMove { data: Column::Three })) {
         Ok(x) => if let Score::Won(0) = x { () } else { assert!(false)},
         _ => assert!(false),
     }
 }
 
+#[test]
+fn test_winning_lines() {
+    let mut cf = ConnectFour::replicate_game("------
+o
+o
+o
+
+xo
+xxo
+xxxo
+------").unwrap();
+    assert!(cf.winning_lines(&Column::Four).is_empty());
+    assert_eq!(cf.drop_stone(&Player::White, Column::Four).unwrap(), Score::Won(0));
+    // horizontal and diagonal at once
+    assert_eq!(cf.winning_lines(&Column::Four), vec![
+        vec![(0, 0), (1, 0), (2, 0), (3, 0)],
+        vec![(3, 0), (4, 1), (5, 2), (6, 3)],
+    ]);
+    // lines through the top stone of another column
+    assert_eq!(cf.winning_lines(&Column::Five), vec![vec![(3, 0), (4, 1), (5, 2), (6, 3)]]);
+    assert_eq!(cf.display_highlighted(&[(3, 0), (4, 1)]), "------\no\no\no\nO\nxO\nxxo\nxxxo\n------");
+
+    // five in a row
+    let mut cf = ConnectFour::replicate_game("------
+x
+x
+
+x
+x
+
+
+------").unwrap();
+    assert_eq!(cf.drop_stone(&Player::Black, Column::Three).unwrap(), Score::Won(0));
+    assert_eq!(cf.winning_lines(&Column::Three), vec![vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)]]);
+}
+
+#[test]
+fn test_turns() {
+    let mut cf = ConnectFour::strict();
+    assert!(!cf.is_permissive());
+    assert_eq!(cf.next_player(), None);
+    // anybody may start, then the players take turns
+    assert_eq!(cf.drop_stone(&Player::White, Column::One).unwrap(), Score::Undecided(0.5));
+    assert_eq!(cf.next_player(), Some(&Player::Black));
+    assert_eq!(cf.drop_stone(&Player::White, Column::One).unwrap_err(), GameError::WrongTurn);
+    for _ in 0..2 {
+        cf.drop_stone(&Player::Black, Column::Two).unwrap();
+        cf.drop_stone(&Player::White, Column::One).unwrap();
+    }
+    cf.drop_stone(&Player::Black, Column::Two).unwrap();
+    assert_eq!(*cf.status(), GameStatus::InProgress);
+    assert_eq!(cf.drop_stone(&Player::White, Column::One).unwrap(), Score::Won(0));
+    assert_eq!(*cf.status(), GameStatus::Won(Player::White));
+    assert_eq!(cf.drop_stone(&Player::Black, Column::Two).unwrap_err(), GameError::GameOver);
+
+    // an earlier stone stays, the game stays won
+    assert_eq!(cf.undrop_stone(&Player::Black, Column::Two).unwrap_err(), GameError::NotLastMove);
+    assert_eq!(*cf.status(), GameStatus::Won(Player::White));
+    // taking back the winning stone
+    cf.undrop_stone(&Player::White, Column::One).unwrap();
+    assert_eq!(*cf.status(), GameStatus::InProgress);
+    assert_eq!(cf.next_player(), Some(&Player::White));
+    // only the last move, the own stone on top of another column is no longer it
+    assert_eq!(cf.undrop_stone(&Player::White, Column::One).unwrap_err(), GameError::NotLastMove);
+    assert_eq!(cf.undrop_stone(&Player::White, Column::Two).unwrap_err(), GameError::NotLastMove);
+
+    // strategies search on a permissive copy of a game played by the rules
+    let plan = cf.display();
+    let g = Arc::new(RwLock::new(cf));
+    let (mv, score) = ConnectFourStrategy::default().find_best_move(g.clone(), &Player::White, 2, true);
+    assert_eq!((mv.unwrap().data(), score), (&Column::One, Some(Score::Won(0))));
+    let mv: Arc<dyn Move<Column>> = Arc::new(ConnectFourMove { data: Column::Three });
+    assert!(ConnectFourStrategy::default().evaluate_move(g.clone(), &Player::Black, mv).is_ok());
+    assert!(!g.read().unwrap().is_permissive());
+    assert_eq!(g.read().unwrap().display(), plan);
+
+    // by the rules on any board
+    let mut cf = ConnectFour::strict_with_rules(5, 4, 3).unwrap();
+    cf.drop_stone(&Player::Black, Column::One).unwrap();
+    assert_eq!(cf.drop_stone(&Player::Black, Column::One).unwrap_err(), GameError::WrongTurn);
+
+    // a permissive game goes on after the win, the status is kept
+    let mut cf = ConnectFour::replicate_game("------
+ooo
+xxx
+
+
+
+
+
+------").unwrap();
+    assert!(cf.is_permissive());
+    cf.drop_stone(&Player::White, Column::One).unwrap();
+    cf.drop_stone(&Player::White, Column::Three).unwrap();
+    assert_eq!(cf.drop_stone(&Player::Black, Column::Two).unwrap(), Score::Won(0));
+    assert_eq!(*cf.status(), GameStatus::Won(Player::Black));
+    cf.undrop_stone(&Player::Black, Column::Two).unwrap();
+    assert_eq!(*cf.status(), GameStatus::Won(Player::White));
+    cf.undrop_stone(&Player::White, Column::Three).unwrap();
+    assert_eq!(*cf.status(), GameStatus::Won(Player::White));
+    cf.undrop_stone(&Player::White, Column::One).unwrap();
+    assert_eq!(*cf.status(), GameStatus::InProgress);
+
+    // a full field is a draw, gray stones win nothing
+    let mut cf = ConnectFour::replicate_game("------
+::::::
+::::::
+::::::
+::::::
+::::::
+::::::
+:::::
+------").unwrap();
+    assert_eq!(*cf.status(), GameStatus::InProgress);
+    cf.set_permissive(false);
+    assert_eq!(cf.drop_stone(&Player::White, Column::Seven).unwrap(), Score::Remis(0));
+    assert_eq!(*cf.status(), GameStatus::Draw);
+    assert_eq!(cf.drop_stone(&Player::Black, Column::Seven).unwrap_err(), GameError::GameOver);
+}
+
+#[test]
+fn test_game_errors() {
+    assert_eq!(Column::from_usize(6).unwrap(), Column::Seven);
+    assert_eq!(Column::from_usize(16).unwrap_err(), GameError::InvalidColumn(16));
+
+    let mut cf = ConnectFour::replicate_game("------
+oxoxox
+
+
+
+
+
+
+------").unwrap();
+    assert_eq!(cf.drop_stone(&Player::White, Column::One).unwrap_err(), GameError::ColumnFull);
+    assert_eq!(cf.drop_stone(&Player::White, Column::from_usize(7).unwrap()).unwrap_err(), GameError::InvalidColumn(7));
+    assert_eq!(cf.undrop_stone(&Player::White, Column::Two).unwrap_err(), GameError::EmptyColumn);
+    let mut bb = BitBoard::try_from(&cf).unwrap();
+    assert_eq!(bb.drop_stone(&Player::White, Column::One).unwrap_err(), GameError::ColumnFull);
+    assert_eq!(bb.undrop_stone(&Player::White, Column::Two).unwrap_err(), GameError::EmptyColumn);
+
+    // lines and characters count from 1
+    assert_eq!(ConnectFour::replicate_game("------\n\nxo?\n\n\n\n\n\n------").err(),
+        Some(GameError::Parse { line: 3, column: 3, message: String::from("unknown stone '?'") }));
+    assert_eq!(ConnectFour::replicate_game("------\nxxxxooo\n\n\n\n\n\n\n------").err(),
+        Some(GameError::Parse { line: 2, column: 7, message: String::from("column is full") }));
+    assert_eq!(ConnectFour::replicate_game("-----\n\n\n\n\n\n\n\n------").err(),
+        Some(GameError::Parse { line: 1, column: 1, message: String::from("expected \"------\"") }));
+}
+
+#[test]
+fn test_dimensions() {
+    assert_eq!(ConnectFour::new().dimensions(), (STANDARD_WIDTH, STANDARD_HEIGHT));
+    assert_eq!(ConnectFour::with_dimensions(0, 6).err(), Some(GameError::InvalidDimensions(0, 6)));
+    assert_eq!(ConnectFour::with_dimensions(17, 6).err(), Some(GameError::InvalidDimensions(17, 6)));
+    assert_eq!(ConnectFour::with_dimensions(7, 0).err(), Some(GameError::InvalidDimensions(7, 0)));
+
+    let mut cf = ConnectFour::with_dimensions(9, 7).unwrap();
+    assert_eq!(cf.possible_moves(&Player::White).len(), 9);
+    for _ in 0..7 {
+        cf.drop_stone(&Player::Gray, Column::Nine).unwrap();
+    }
+    assert_eq!(cf.drop_stone(&Player::White, Column::Nine).unwrap_err(), GameError::ColumnFull);
+    assert_eq!(cf.possible_moves(&Player::White).len(), 8);
+
+    // the winning line is in the columns a standard board does not have
+    let game = ConnectFour::replicate_game_with_dimensions("------
+o
+
+
+
+
+
+xo
+xo
+xo
+------", 9, 7).unwrap();
+    let strategy = ConnectFourStrategy::default();
+    match strategy.find_best_move(Arc::new(RwLock::new(game)), &Player::Black, 0, false) {
+        (Some(mv), Some(score)) => {
+            assert_eq!(*mv.data(), Column::Six);
+            assert_eq!(score, Score::Won(0));
+        },
+        _ => panic!("no move"),
+    }
+
+    // the brute force strategy solves small boards
+    let game = ConnectFour::replicate_game_with_dimensions("------
+ox
+ox
+ox
+
+------", 4, 3).unwrap();
+    let strategy = BruteForceStrategy::new(BruteForceConfig { dump: None, ..BruteForceConfig::default() });
+    match strategy.find_best_move(Arc::new(RwLock::new(game)), &Player::White, 3, true) {
+        (Some(mv), Some(score)) => {
+            assert_eq!(*mv.data(), Column::Four);
+            assert_eq!(score, Score::Won(0));
+        },
+        _ => panic!("no move"),
+    }
+    // a board too large for its hashes is left alone
+    let g = Arc::new(RwLock::new(ConnectFour::with_dimensions(8, 8).unwrap()));
+    assert!(strategy.find_best_move(g, &Player::White, 3, true).0.is_none());
+}
+
+#[test]
+fn test_win_length() {
+    assert_eq!(ConnectFour::new().win_length(), STANDARD_WIN_LENGTH);
+    assert_eq!(ConnectFour::with_rules(7, 6, 1).err(), Some(GameError::InvalidWinLength(1)));
+    assert_eq!(ConnectFour::with_rules(4, 3, 5).err(), Some(GameError::InvalidWinLength(5)));
+
+    // connect three
+    let mut cf = ConnectFour::with_rules(5, 4, 3).unwrap();
+    assert_eq!(cf.drop_stone(&Player::White, Column::One).unwrap(), Score::Undecided(0.5));
+    assert_eq!(cf.drop_stone(&Player::White, Column::Two).unwrap(), Score::Undecided(0.5));
+    assert_eq!(cf.drop_stone(&Player::White, Column::Three).unwrap(), Score::Won(0));
+    assert_eq!(cf.winning_lines(&Column::Three), vec![vec![(0, 0), (1, 0), (2, 0)]]);
+
+    // connect five
+    let mut cf = ConnectFour::with_rules(7, 6, 5).unwrap();
+    for _ in 0..4 {
+        assert_eq!(cf.drop_stone(&Player::Black, Column::Seven).unwrap(), Score::Undecided(0.5));
+    }
+    assert!(cf.winning_lines(&Column::Seven).is_empty());
+    assert_eq!(cf.drop_stone(&Player::Black, Column::Seven).unwrap(), Score::Won(0));
+
+    // a stone is dead once there is no room left for a line of win_length through it
+    let mut cf = ConnectFour::replicate_game_with_rules("------
+o
+
+
+
+
+------", 5, 1, 4).unwrap();
+    let (_, grayed) = cf.make_shading_move(&Player::Black, Arc::new(ConnectFourMove { data: Column::Five })).unwrap();
+    assert!(grayed.is_empty());
+    let mut cf = ConnectFour::replicate_game_with_rules("------
+o
+
+
+
+
+------", 5, 1, 5).unwrap();
+    let (_, grayed) = cf.make_shading_move(&Player::Black, Arc::new(ConnectFourMove { data: Column::Five })).unwrap();
+    assert_eq!(grayed, vec![(0, 0)]);
+
+    let game = ConnectFour::replicate_game_with_rules("------
+ox
+ox
+
+
+
+------", 5, 4, 3).unwrap();
+    let strategy = ConnectFourStrategy::default();
+    match strategy.find_best_move(Arc::new(RwLock::new(game)), &Player::White, 0, false) {
+        (Some(mv), Some(score)) => {
+            assert_eq!(*mv.data(), Column::Three);
+            assert_eq!(score, Score::Won(0));
+        },
+        _ => panic!("no move"),
+    }
+}
+
+#[test]
+fn test_pop_out() {
+    let mut cf = ConnectFour::new();
+    cf.drop_stone(&Player::White, Column::One).unwrap();
+    assert_eq!(cf.pop_stone(&Player::White, Column::One).unwrap_err(), GameError::PopNotAllowed);
+
+    cf.set_pop_out(true);
+    cf.drop_stone(&Player::Black, Column::One).unwrap();
+    assert_eq!(cf.possible_moves(&Player::White).len(), 8);
+    assert_eq!(cf.possible_moves(&Player::Black).len(), 7);
+    assert_eq!(cf.pop_stone(&Player::Black, Column::One).unwrap_err(), GameError::NotYourStone);
+    assert_eq!(cf.pop_stone(&Player::White, Column::Two).unwrap_err(), GameError::EmptyColumn);
+    assert_eq!(cf.pop_stone(&Player::White, Column::One).unwrap(), Score::Undecided(0.5));
+    assert_eq!(cf.display(), "------\nx\n\n\n\n\n\n\n------");
+    cf.unpop_stone(&Player::White, Column::One).unwrap();
+    assert_eq!(cf.display(), "------\nox\n\n\n\n\n\n\n------");
+
+    // popping connects the stones of both players, the one who pops wins
+    let plan = "------
+oxo
+xo
+xo
+xo
+
+
+
+------";
+    let mut cf = ConnectFour::replicate_game(plan).unwrap();
+    cf.set_pop_out(true);
+    assert_eq!(cf.pop_stone(&Player::White, Column::One).unwrap(), Score::Won(0));
+    assert_eq!(*cf.status(), GameStatus::Won(Player::White));
+    assert_eq!(cf.lines_in_column(&Column::One, &Player::White), vec![vec![(0, 1), (1, 1), (2, 1), (3, 1)]]);
+    assert_eq!(cf.lines_in_column(&Column::One, &Player::Black), vec![vec![(0, 0), (1, 0), (2, 0), (3, 0)]]);
+    cf.unpop_stone(&Player::White, Column::One).unwrap();
+    assert_eq!(*cf.status(), GameStatus::InProgress);
+    assert_eq!(cf.display(), ConnectFour::replicate_game(plan).unwrap().display());
+
+    // both strategies find the pop
+    for strategy in [
+        Box::new(ConnectFourStrategy::default()) as Box<dyn Strategy<Column,Vec<Vec<Option<Player>>>>>,
+        Box::new(BruteForceStrategy::new(BruteForceConfig { dump: None, ..BruteForceConfig::default() })),
+    ].iter() {
+        let mut cf = ConnectFour::replicate_game(plan).unwrap();
+        cf.set_pop_out(true);
+        match strategy.find_best_move(Arc::new(RwLock::new(cf)), &Player::White, 1, true) {
+            (Some(mv), Some(score)) => {
+                assert_eq!((mv.kind(), mv.data().clone()), (MoveKind::Pop, Column::One));
+                assert_eq!(score, Score::Won(0));
+            },
+            _ => panic!("no move"),
+        }
+    }
+
+    // connecting the opponent's stones only loses
+    let plan = "------
+oxo
+xx
+xo
+xo
+
+
+
+------";
+    let mut cf = ConnectFour::replicate_game(plan).unwrap();
+    cf.set_pop_out(true);
+    assert_eq!(cf.pop_stone(&Player::White, Column::One).unwrap(), Score::Lost(0));
+    assert_eq!(*cf.status(), GameStatus::Won(Player::Black));
+    cf.unpop_stone(&Player::White, Column::One).unwrap();
+    match ConnectFourStrategy::default().find_best_move(Arc::new(RwLock::new(cf)), &Player::White, 1, true) {
+        (Some(mv), _) => assert_eq!(mv.kind(), MoveKind::Drop),
+        _ => panic!("no move"),
+    }
+}
+
+#[test]
+fn test_move_string() {
+    let mut cf = ConnectFour::from_move_string("4453").unwrap();
+    assert_eq!(cf.display(), "------\n\n\nx\nox\no\n\n\n------");
+    assert_eq!(cf.history()[1], (Player::Black, MoveKind::Drop, Column::Four));
+    assert_eq!(cf.move_string(), Some(String::from("4453")));
+    // the game goes on with the next player
+    cf.play_move_string("3").unwrap();
+    assert_eq!(cf.next_player(), Some(&Player::Black));
+    cf.undrop_stone(&Player::White, Column::Three).unwrap();
+    assert_eq!(cf.move_string(), Some(String::from("4453")));
+
+    assert_eq!(ConnectFour::from_move_string("1212121").unwrap().move_string(), Some(String::from("1212121")));
+    assert_eq!(ConnectFour::from_move_string("12121212").err(),
+        Some(GameError::Parse { line: 1, column: 8, message: String::from("game is over") }));
+    assert_eq!(ConnectFour::from_move_string("408").err(),
+        Some(GameError::Parse { line: 1, column: 2, message: String::from("unknown move '0'") }));
+    assert_eq!(ConnectFour::from_move_string("8").err(),
+        Some(GameError::Parse { line: 1, column: 1, message: String::from("there is no column 8") }));
+    let mut narrow = ConnectFour::with_rules(5, 6, 4).unwrap();
+    assert_eq!(narrow.play_move_string("56").err(),
+        Some(GameError::Parse { line: 1, column: 2, message: String::from("there is no column 6") }));
+    // nothing is played of a string with an error
+    assert_eq!(narrow.dropped_stones(), 0);
+    assert_eq!(narrow.next_player(), None);
+    assert_eq!(cf.play_move_string("3318").err(),
+        Some(GameError::Parse { line: 1, column: 4, message: String::from("there is no column 8") }));
+    assert_eq!(cf.play_move_string("3333333").err(),
+        Some(GameError::Parse { line: 1, column: 6, message: String::from("column is full") }));
+    assert_eq!(cf.move_string(), Some(String::from("4453")));
+    assert_eq!(ConnectFour::from_move_string("1111111").err(),
+        Some(GameError::Parse { line: 1, column: 7, message: String::from("column is full") }));
+
+    // moves the notation has no digits for
+    let mut cf = ConnectFour::from_move_string("4").unwrap();
+    cf.set_pop_out(true);
+    cf.pop_stone(&Player::Black, Column::Four).unwrap_err();
+    cf.drop_stone(&Player::Black, Column::One).unwrap();
+    cf.pop_stone(&Player::White, Column::Four).unwrap();
+    assert_eq!(cf.move_string(), None);
+    cf.unpop_stone(&Player::White, Column::Four).unwrap();
+    assert_eq!(cf.move_string(), Some(String::from("41")));
+    cf.drop_stone(&Player::Black, Column::One).unwrap();
+    assert_eq!(cf.move_string(), None);
+}
+
+#[test]
+fn test_parse_position() {
+    let plan = "------
+
+ox
+x
+o
+
+
+
+------";
+    let (game, to_move) = ConnectFour::parse_position(plan).unwrap();
+    assert_eq!(to_move, Player::White);
+    assert_eq!(game.next_player(), Some(&Player::White));
+    assert_eq!(game.position_string(), "/ox/x/o/// o");
+    // both layouts round-trip
+    let (again, to_move) = ConnectFour::parse_position(&game.position_string()).unwrap();
+    assert_eq!((again.display(), to_move), (String::from(plan), Player::White));
+    let (again, to_move) = ConnectFour::parse_position(&format!("{}\n", game.display())).unwrap();
+    assert_eq!((again.position_string(), to_move), (game.position_string(), Player::White));
+
+    // the side to move
+    assert_eq!(ConnectFour::parse_position("/ox/x/o/// x").unwrap().1, Player::Black);
+    assert_eq!(ConnectFour::parse_position("/ox/x/o/x//").unwrap().1, Player::White);
+    assert_eq!(ConnectFour::parse_position("/ox/x/o/o//").unwrap().1, Player::Black);
+    assert_eq!(ConnectFour::parse_position("/ox/x/o/o// o").err(), Some(GameError::WrongTurn));
+    assert_eq!(ConnectFour::parse_position("/ox/x/o/// b").err(),
+        Some(GameError::Parse { line: 1, column: 12, message: String::from("unknown player 'b'") }));
+    // a board as wide as there are columns
+    assert_eq!(ConnectFour::parse_position("x/o/x/o/x/o/x/o/x").unwrap().0.dimensions(), (9, STANDARD_HEIGHT));
+
+    // broken positions
+    assert_eq!(ConnectFour::parse_position("").err(),
+        Some(GameError::Parse { line: 1, column: 1, message: String::from("expected a position") }));
+    assert_eq!(ConnectFour::parse_position("/ox/xq").err(),
+        Some(GameError::Parse { line: 1, column: 6, message: String::from("unknown stone 'q'") }));
+    assert_eq!(ConnectFour::parse_position("------\nx\noxoxoxo\n------").err(),
+        Some(GameError::Parse { line: 3, column: 7, message: String::from("column is full") }));
+    assert_eq!(ConnectFour::parse_position("------\nx\no").err(),
+        Some(GameError::Parse { line: 3, column: 1, message: String::from("expected \"------\"") }));
+    assert_eq!(ConnectFour::parse_position("------").err(),
+        Some(GameError::Parse { line: 2, column: 1, message: String::from("expected \"------\"") }));
+    assert_eq!(ConnectFour::parse_position("xxx/o//").err(), Some(GameError::Unbalanced { black: 3, white: 1 }));
+    // unless gray stones make up for it
+    assert_eq!(ConnectFour::parse_position("xxx/o:/").unwrap().1, Player::White);
+    assert_eq!(ConnectFour::parse_position("xxxx/ooo//").err(), Some(GameError::GameOver));
+
+    // other boards
+    let (game, to_move) = ConnectFour::parse_position_with_rules("------\nx\noxoxoxo\n------", 7, 4).unwrap();
+    assert_eq!(game.dimensions(), (2, 7));
+    assert_eq!(to_move, Player::White);
+    assert_eq!(ConnectFour::parse_position_with_rules("xxxx/ooo//", 6, 5).unwrap().1, Player::White);
+}
+
 #[test]
 fn test_possible_moves() {
     let mut cf = ConnectFour::new();
     let p = Player::Black;
     let pm = cf.possible_moves(&p);
-    assert!(pm.len()==ConnectFour::width());
+    assert!(pm.len()==STANDARD_WIDTH);
     assert!(*pm[3].data() == Column::Four);
 
     for _ in 0..6 {
         let _ = cf.drop_stone(&p, Column::Four);
     }
-    let pm:Vec<Rc<dyn Move<Column>>> = cf.possible_moves(&p);
+    let pm:Vec<Arc<dyn Move<Column>>> = cf.possible_moves(&p);
     //println!("{:?}", &cf.field);
     for x in &pm {
         println!("{:?}", &x.data());
     }
-    assert!(pm.len()==ConnectFour::width()-1);
+    assert!(pm.len()==STANDARD_WIDTH-1);
     assert!(*pm[3].data() == Column::Five);
 }
 
@@ -91,6 +537,9 @@ fn test_find_best_move() {
             my_tabu_koeff: 0.0,
             opp_tabu_koeff: 0.0,
             tabu_defense_koeff: 0.0,
+            nthreads: 1,
+            book: None,
+            endgame: None,
     };
 
     // recognize a winner
@@ -103,7 +552,7 @@ fn test_find_best_move() {
         };
     }
     if let (Some(mv), Some(score)) = strategy.find_best_move(
-            Rc::new(RefCell::new(game)), &white, 0, false) {
+            Arc::new(RwLock::new(game)), &white, 0, false) {
         println!("{:?} {:?}", mv.data(), score);
         assert!(Score::Won(0) == score);
         assert!(*mv.data() == Column::Six);
@@ -119,7 +568,7 @@ fn test_find_best_move() {
         };
     }
     if let (Some(mv), Some(score)) = strategy.find_best_move(
-            Rc::new(RefCell::new(game)), &white, 1, false) {
+            Arc::new(RwLock::new(game)), &white, 1, false) {
         println!("{:?} {:?}", mv.data(), score);
         match score {
             Score::Undecided(p) => assert!(p == 0.5),
@@ -131,7 +580,7 @@ fn test_find_best_move() {
     // first move
     game = ConnectFour::new();
     if let (Some(mv), Some(score)) = strategy.find_best_move(
-            Rc::new(RefCell::new(game)), &white, 2, true) {
+            Arc::new(RwLock::new(game)), &white, 2, true) {
         println!("{:?} {:?} vs {}", mv.data(), score,
                             15 as f32 * strategy.mscore_koeff * strategy.nscore_koeff
                         + 15 as f32 * strategy.oscore_koeff * strategy.nscore_koeff);
@@ -151,7 +600,7 @@ fn test_find_best_move() {
     game.drop_stone(&white, Column::Five).unwrap();
     // below 3 moves ahead premeditation the danger is not recognized!
     if let (Some(mv), Some(score)) = strategy.find_best_move(
-            Rc::new(RefCell::new(game)), &black, 3, true) {
+            Arc::new(RwLock::new(game)), &black, 3, true) {
         println!("trap {:?} {:?}", mv.data(), score);
         assert!(Score::Undecided(7.8) == score);
         // one may think Three is best - but: computers says no. Three scores 7.5.
@@ -165,7 +614,7 @@ fn test_find_best_move() {
     game.drop_stone(&white, Column::Five).unwrap();
     game.drop_stone(&black, Column::Five).unwrap();
     if let (Some(mv), Some(score)) = strategy.find_best_move(
-            Rc::new(RefCell::new(game)), &white, 4, true) {
+            Arc::new(RwLock::new(game)), &white, 4, true) {
         println!("opportunity {:?} {:?}", mv.data(), score);
         assert!(Score::Won(2) == score);
         assert!(*mv.data() == Column::Three);
@@ -186,13 +635,16 @@ fn test_evaluate_move() {
             my_tabu_koeff: 0.0,
             opp_tabu_koeff: 0.0,
             tabu_defense_koeff: 0.0,
+            nthreads: 1,
+            book: None,
+            endgame: None,
     };
 
     let expected = 10 as f32 * s.mscore_koeff * s.nscore_koeff
                     + 0 as f32 * s.mscore_koeff
                     + 10 as f32 * s.oscore_koeff * s.nscore_koeff
                     + 1 as f32 * s.oscore_koeff;
-    if let Ok(eval) = s.evaluate_move(Rc::new(RefCell::new(g)), &white, Rc::new(mv)) {
+    if let Ok(eval) = s.evaluate_move(Arc::new(RwLock::new(g)), &white, Arc::new(mv)) {
         println!("expected score {} vs calculated {}", expected, eval);
         assert!(eval == expected)
     } else { assert!(false) }
@@ -211,9 +663,9 @@ fn test_evaluate_move() {
                     + 0 as f32 * s.mscore_koeff
                     + 8 as f32 * s.oscore_koeff * s.nscore_koeff
                     + 2 as f32 * s.oscore_koeff;
-    if let Ok(eval) = s.evaluate_move(Rc::new(RefCell::new(g)),
+    if let Ok(eval) = s.evaluate_move(Arc::new(RwLock::new(g)),
                                         &black,
-                                        Rc::new(ConnectFourMove { data: Column::One, })) {
+                                        Arc::new(ConnectFourMove { data: Column::One, })) {
         println!("expected score {} vs calculated {}", expected, eval);
         assert!((eval-expected).abs() < TOLERANCE);
     } else { assert!(false) }
@@ -249,6 +701,9 @@ fn test_evaluate_tabu_move() {
         my_tabu_koeff: -1.0,
         opp_tabu_koeff: 8.0,
         tabu_defense_koeff: 0.5,
+        nthreads: 1,
+        book: None,
+        endgame: None,
     };
     let game = replicate_game("------
 ox
@@ -256,13 +711,13 @@ ox
 ox
 xo
 ");
-    let g = Rc::new(RefCell::new(game));
+    let g = Arc::new(RwLock::new(game));
     let h = g.clone();
-    match strategy.evaluate_move(g, &Player::Black, Rc::new(ConnectFourMove { data: Column::Two })) {
+    match strategy.evaluate_move(g, &Player::Black, Arc::new(ConnectFourMove { data: Column::Two })) {
         Ok(e) => { println!("{}", e); assert!(e==8.0) },
         _ => assert!(false),
     }
-    match strategy.evaluate_move(h.clone(), &Player::White, Rc::new(ConnectFourMove { data: Column::Two })) {
+    match strategy.evaluate_move(h.clone(), &Player::White, Arc::new(ConnectFourMove { data: Column::Two })) {
         Ok(e) => { println!("{}", e); assert!(e==0.5) },
         _ => assert!(false),
     }
@@ -278,6 +733,9 @@ fn evaluate_complex_move() {
         my_tabu_koeff: -10.0,
         opp_tabu_koeff: 10.0,
         tabu_defense_koeff: 0.25,
+        nthreads: 1,
+        book: None,
+        endgame: None,
     };
 
     let game = replicate_game("------
@@ -289,13 +747,13 @@ xxoxxx
 
 oxo
 ------");
-    let g = Rc::new(RefCell::new(game));
+    let g = Arc::new(RwLock::new(game));
     for u in 0..7 {
         println!("{:?} {}",
-            Column::from_usize(u),
+            Column::from_usize(u).unwrap(),
             strategy.evaluate_move(g.clone(),
                 &Player::White,
-                Rc::new(ConnectFourMove { data: Column::from_usize(u) }
+                Arc::new(ConnectFourMove { data: Column::from_usize(u).unwrap() }
             )).unwrap_or(0.0)
         );
     }
@@ -324,6 +782,9 @@ fn evaluate_another_complex_move() {
         my_tabu_koeff: -10.0,
         opp_tabu_koeff: 10.0,
         tabu_defense_koeff: 0.25,
+        nthreads: 1,
+        book: None,
+        endgame: None,
     };
 
     let game = replicate_game("------
@@ -357,6 +818,9 @@ x
         my_tabu_koeff: -10.0,
         opp_tabu_koeff: 10.0,
         tabu_defense_koeff: 0.25,
+        nthreads: 1,
+        book: None,
+        endgame: None,
     };
     complex_evaluation(game, &strategy, &Player::White, Column::Three, 9.4);
 
@@ -389,19 +853,22 @@ oooxo
         my_tabu_koeff: -10.0,
         opp_tabu_koeff: 5.0, //10.0
         tabu_defense_koeff: 0.25,
+        nthreads: 1,
+        book: None,
+        endgame: None,
     };
     complex_evaluation(game, &strategy, &Player::White, Column::Four, 11.4);
 }
 
 fn complex_evaluation(game:ConnectFour, strategy:&ConnectFourStrategy, player:&Player,
                       expected_column:Column, expected_score:f32) {
-    let g = Rc::new(RefCell::new(game));
+    let g = Arc::new(RwLock::new(game));
     for u in 0..7 {
         println!("{:?} {}",
-            Column::from_usize(u),
+            Column::from_usize(u).unwrap(),
             strategy.evaluate_move(g.clone(),
                 player,
-                Rc::new(ConnectFourMove { data: Column::from_usize(u) }
+                Arc::new(ConnectFourMove { data: Column::from_usize(u).unwrap() }
             )).unwrap_or(0.0)
         );
     }
@@ -427,7 +894,7 @@ xxoo
 ox
 
 ------");
-    let g = Rc::new(RefCell::new(game));
+    let g = Arc::new(RwLock::new(game));
     match strategy.find_best_move(g.clone(), &Player::White, 6, true) {
         (Some(mv), Some(score)) => {
             println!("{:?} {:?}", mv.data(), score);
@@ -467,11 +934,14 @@ fn assert_best_move(strategy: Option<ConnectFourStrategy>,
             my_tabu_koeff: 0.0,
             opp_tabu_koeff: 0.0,
             tabu_defense_koeff: 0.0,
+            nthreads: 1,
+            book: None,
+            endgame: None,
         },
     };
     
     if let (Some(mv), Some(calculated)) = strategy.find_best_move(
-            Rc::new(RefCell::new(game)), player, 4, true) {
+            Arc::new(RwLock::new(game)), player, 4, true) {
         println!("{:?} {:?}", *mv.data(), calculated);
         assert!(calculated == score);
         assert!(*mv.data() == col);
@@ -490,7 +960,7 @@ fn replicate_game(plan: &str) -> ConnectFour {
                             'o' => &Player::White,
                             what => { println!("{}, {}", what, i); assert!(false); &Player::Black },
                         },
-                        Column::from_usize(i-1)
+                        Column::from_usize(i-1).unwrap()
                     ).unwrap(); 
                 }
             },
@@ -500,13 +970,22 @@ fn replicate_game(plan: &str) -> ConnectFour {
     g
 }
 
+// tests run in parallel, each one dumps to a file of its own
+fn dump_file(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("connectfour-strdmp-{}-{}", name, std::process::id()));
+    String::from(path.to_str().unwrap())
+}
+
 #[test]
 fn test_bruteforce() {
 
     let nworker = 1;
     let player = Player::Black;
 
-    let strategy = BruteForceStrategy::new(nworker);
+    let dumpfile = dump_file("bruteforce");
+    let strategy = BruteForceStrategy::new(BruteForceConfig {
+        nworkers: nworker, dump: Some(dumpfile.clone()), ..BruteForceConfig::default()
+    });
     
     let game = ConnectFour::replicate_game("------
 
@@ -516,12 +995,12 @@ oxox
 oxox
 xo:x
 
-------");
+------").unwrap();
 
     // with some wisdom
-    let g = Rc::new(RefCell::new(game.clone()));
+    let g = Arc::new(RwLock::new(game.clone()));
     let toplimit = 4;
-    let (h,s) = hash_from_state(g.clone().borrow().state());
+    let (h,s) = hash_from_state(g.clone().read().unwrap().state(), STANDARD_HEIGHT);
     assert!((h,s) == (209874779512449794048, false), "{} is not 209874779512449794048", h);
     match strategy.find_best_move(g.clone(), &player, toplimit, true) {
         (Some(mv), Some(score)) => {
@@ -530,7 +1009,7 @@ xo:x
             if let Score::Won(n) = score { assert!(n == 4); }
             else { assert!(false); }
 
-            let dump = std::fs::read_to_string(STRDMP).unwrap();
+            let dump = std::fs::read_to_string(&dumpfile).unwrap();
             let expected = std::fs::read_to_string("tests/data/toplimit4").unwrap();
             assert!(dump == expected,
                     std::fs::write("tests/data/toplimit4~", dump));
@@ -539,7 +1018,7 @@ xo:x
     };
 
     // with no wisdom
-    let g = Rc::new(RefCell::new(game.clone()));
+    let g = Arc::new(RwLock::new(game.clone()));
     let toplimit = 0;
 
     match strategy.find_best_move(g.clone(), &player, toplimit, true) {
@@ -548,7 +1027,7 @@ xo:x
             if let Score::Won(n) = score { assert!(n == 4); }
             else { assert!(false); }
 
-            let dump = std::fs::read_to_string(STRDMP).unwrap();
+            let dump = std::fs::read_to_string(&dumpfile).unwrap();
             let expected = std::fs::read_to_string("tests/data/toplimit0").unwrap();
             assert!(dump == expected,
                 std::fs::write("tests/data/toplimit0~", dump).unwrap()
@@ -564,7 +1043,10 @@ fn test_bruteforce_2() {
     let nworker = 1;
     let player = Player::Black;
 
-    let strategy = BruteForceStrategy::new(nworker);
+    let dumpfile = dump_file("bruteforce_2");
+    let strategy = BruteForceStrategy::new(BruteForceConfig {
+        nworkers: nworker, dump: Some(dumpfile.clone()), ..BruteForceConfig::default()
+    });
     
     let game = ConnectFour::replicate_game("------
 
@@ -574,12 +1056,12 @@ xo
 
 x
 
-------");
+------").unwrap();
 
     // with some wisdom
-    let g = Rc::new(RefCell::new(game.clone()));
+    let g = Arc::new(RwLock::new(game.clone()));
     let toplimit = 4;
-    let (h,s) = hash_from_state(g.clone().borrow().state());
+    let (h,s) = hash_from_state(g.clone().read().unwrap().state(), STANDARD_HEIGHT);
     assert!((h,s) == (2305843421530558464, false));
     match strategy.find_best_move(g.clone(), &player, toplimit, true) {
         (Some(mv), Some(score)) => {
@@ -588,7 +1070,7 @@ x
             if let Score::Won(n) = score { assert!(n == 2); }
             else { assert!(false); }
 
-            let dump = std::fs::read_to_string(STRDMP).unwrap();
+            let dump = std::fs::read_to_string(&dumpfile).unwrap();
             let expected = std::fs::read_to_string("tests/data/toplimit4_2").unwrap();
             assert!(dump == expected,
                     std::fs::write("tests/data/toplimit4_2~", dump));
@@ -599,7 +1081,7 @@ x
     // with no wisdom
     // but - alas! - even no wisdom involves a two steps ahead inquiry
     // explaining the sameness of files toplimit0 and toplimit4
-    let g = Rc::new(RefCell::new(game.clone()));
+    let g = Arc::new(RwLock::new(game.clone()));
     let toplimit = 0;
 
     match strategy.find_best_move(g.clone(), &player, toplimit, true) {
@@ -608,7 +1090,7 @@ x
             if let Score::Won(n) = score { assert!(n == 2); }
             else { assert!(false); }
 
-            let dump = std::fs::read_to_string(STRDMP).unwrap();
+            let dump = std::fs::read_to_string(&dumpfile).unwrap();
             let expected = std::fs::read_to_string("tests/data/toplimit0_2").unwrap();
             assert!(dump == expected,
                 std::fs::write("tests/data/toplimit0_2~", dump).unwrap()
@@ -638,42 +1120,46 @@ xoox
 ox
 :x
 ------";
-    let game = ConnectFour::replicate_game(expected_before_move_six);
+    let game = ConnectFour::replicate_game(expected_before_move_six).unwrap();
     let mut mg = game.clone();
-    let (_score,grayed) = mg.make_shading_move(&Player::Black, Rc::new(ConnectFourMove { data: Column::Six })).unwrap();
+    let (_score,grayed) = mg.make_shading_move(&Player::Black, Arc::new(ConnectFourMove { data: Column::Six })).unwrap();
     assert!(mg.display().eq(expected_after_move_six), mg.display());
    
-    let (hash, swapped) = hash_from_state(mg.state());
-    let (expected_hash, eswapped) = hash_from_state(ConnectFour::replicate_game(expected_after_move_six).state());
+    let (hash, swapped) = hash_from_state(mg.state(), STANDARD_HEIGHT);
+    let (expected_hash, eswapped) = hash_from_state(ConnectFour::replicate_game(expected_after_move_six).unwrap().state(), STANDARD_HEIGHT);
     assert!(hash == expected_hash);
     assert!(eswapped && swapped);
     assert!(hash == 708365348734296165224459, "{} is not 708365348734296165224459", hash);
 
     // undo
-    mg.withdraw_move_unshading(&Player::Black, Rc::new(ConnectFourMove { data: Column::Six }), grayed);
+    mg.withdraw_move_unshading(&Player::Black, Arc::new(ConnectFourMove { data: Column::Six }), grayed);
     assert!(mg.display().eq(expected_before_move_six), mg.display());
    
-    let (hash, swapped) = hash_from_state(mg.state());
-    let (expected_hash, eswapped) = hash_from_state(ConnectFour::replicate_game(expected_before_move_six).state());
+    let (hash, swapped) = hash_from_state(mg.state(), STANDARD_HEIGHT);
+    let (expected_hash, eswapped) = hash_from_state(ConnectFour::replicate_game(expected_before_move_six).unwrap().state(), STANDARD_HEIGHT);
     assert!(hash == expected_hash);
     assert!(eswapped && swapped);
     assert!(hash == 708365348734296165191689, "{} is not 708365348734296165191689", hash);
 
     // at last check shading in action
-    let g = Rc::new(RefCell::new(game.clone()));
+    let g = Arc::new(RwLock::new(game.clone()));
     let toplimit = 0;
     let nworker = 1;
    
     let player = Player::Black;
-    let strategy = BruteForceStrategy::new(nworker);
+    let dumpfile = dump_file("graying_1");
+    let strategy = BruteForceStrategy::new(BruteForceConfig {
+        nworkers: nworker, dump: Some(dumpfile.clone()), ..BruteForceConfig::default()
+    });
  
     match strategy.find_best_move(g.clone(), &player, toplimit, true) {
         (Some(mv), Some(score)) => {
-            assert!(Column::Six == *mv.data());
+            // the hash is swapped, Six is the winning move on the mirrored board
+            assert!(Column::Two == *mv.data());
             if let Score::Won(n) = score { assert!(n == 4); }
             else { assert!(false); }
 
-            let dump = std::fs::read_to_string(STRDMP).unwrap();
+            let dump = std::fs::read_to_string(&dumpfile).unwrap();
             let expected = std::fs::read_to_string("tests/data/shading").unwrap();
             assert!(dump == expected,
                 std::fs::write("tests/data/shading~", dump).unwrap()
@@ -703,31 +1189,338 @@ xo
 o
 
 ------";
-    let game = ConnectFour::replicate_game(expected_before_move_two);
+    let game = ConnectFour::replicate_game(expected_before_move_two).unwrap();
     let mut mg = game.clone();
-    let (_score,grayed) = mg.make_shading_move(&Player::Black, Rc::new(ConnectFourMove { data: Column::Two })).unwrap();
+    let (_score,grayed) = mg.make_shading_move(&Player::Black, Arc::new(ConnectFourMove { data: Column::Two })).unwrap();
     assert!(mg.display().eq(expected_after_move_two), mg.display());
 
-    let hash = hash_from_state(mg.state());
-    let expected_hash = hash_from_state(ConnectFour::replicate_game(expected_after_move_two).state());
+    let hash = hash_from_state(mg.state(), STANDARD_HEIGHT);
+    let expected_hash = hash_from_state(ConnectFour::replicate_game(expected_after_move_two).unwrap().state(), STANDARD_HEIGHT);
     assert!(hash == expected_hash);
 
     // undo
-    mg.withdraw_move_unshading(&Player::Black, Rc::new(ConnectFourMove { data: Column::Two }), grayed);
+    mg.withdraw_move_unshading(&Player::Black, Arc::new(ConnectFourMove { data: Column::Two }), grayed);
     assert!(mg.display().eq(expected_before_move_two), mg.display());
 
-    let hash = hash_from_state(mg.state());
-    let expected_hash = hash_from_state(ConnectFour::replicate_game(expected_before_move_two).state());
-    assert!(hash == expected_hash);
+    let hash = hash_from_state(mg.state(), STANDARD_HEIGHT);
+    let expected_hash = hash_from_state(ConnectFour::replicate_game(expected_before_move_two).unwrap().state(), STANDARD_HEIGHT);
+    assert_eq!(hash, expected_hash);
+    assert_eq!(mg.hash(), Some(hash));
+}
+
+#[test]
+fn test_incremental_hash() {
+    // a walk of moves and take-backs picked by a little linear congruential generator
+    let mut seed: u64 = 4453;
+    let mut pick = |n: usize| -> usize {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize % n
+    };
+    for &(width, height) in &[(7, 6), (4, 4), (5, 3), (1, 5), (9, 7)] {
+        let mut cf = ConnectFour::with_dimensions(width, height).unwrap();
+        cf.set_pop_out(true);
+        assert_eq!(cf.hash(), Some(hash_from_state(cf.state(), height)));
+        let mut p = Player::White;
+        let mut taken = Vec::new();
+        for _ in 0..400 {
+            let options = cf.possible_moves(&p);
+            if pick(3) == 0 || options.is_empty() {
+                // take back the last move
+                match taken.pop() {
+                    Some((q, mv, Some(grayed))) => cf.withdraw_move_unshading(&q, mv, grayed),
+                    Some((q, mv, None)) => cf.withdraw_move(&q, mv).unwrap(),
+                    None => continue,
+                }
+            } else {
+                let mv = Arc::clone(&options[pick(options.len())]);
+                if mv.kind() == MoveKind::Drop && pick(2) == 0 {
+                    let (_, grayed) = cf.make_shading_move(&p, Arc::clone(&mv)).unwrap();
+                    taken.push((p.clone(), mv, Some(grayed)));
+                } else {
+                    cf.make_move(&p, Arc::clone(&mv)).unwrap();
+                    taken.push((p.clone(), mv, None));
+                }
+                p = p.opponent().clone();
+            }
+            assert_eq!(cf.hash(), Some(hash_from_state(cf.state(), height)), "\n{}", cf.display());
+        }
+    }
+
+    // more cells than digits
+    assert_eq!(ConnectFour::with_dimensions(16, 4).unwrap().hash(), None);
+}
+
+#[test]
+fn test_position_key() {
+    let rules = (STANDARD_WIDTH, STANDARD_HEIGHT, STANDARD_WIN_LENGTH);
+    let game = ConnectFour::replicate_game("------
+ox
+
+:o
+
+x
+
+
+------").unwrap();
+    // a longer column on the left, the key is taken from the other side
+    let (key, mirrored) = PositionKey::of(&game).unwrap();
+    assert!(mirrored);
+    assert_eq!((key.hash(), mirrored), hash_from_state(game.state(), STANDARD_HEIGHT));
+    let decoded = key.game(rules).unwrap();
+    assert_eq!(decoded.display(), "------
+
+
+x
+
+:o
+
+ox
+------");
+    assert_eq!(PositionKey::of(&decoded), Some((key, false)));
+    assert_eq!(PositionKey::from_hash(key.hash(), rules), Ok(key));
+
+    // the hash of the field as it is is no key, but still a position
+    let unmirrored = 1 + 2 * 4 + 3 * 4i128.pow(12) + 4i128.pow(13) + 2 * 4i128.pow(24);
+    assert_eq!(PositionKey::from_hash(unmirrored, rules), Err(GameError::InvalidKey(unmirrored)));
+    assert_eq!(PositionKey::decode(unmirrored, rules).unwrap().display(), game.display());
+
+    // no stones above an empty cell, no more digits than cells
+    for hash in &[4, 2 + 3 * 16, -1, 4i128.pow(42)] {
+        assert_eq!(PositionKey::from_hash(*hash, rules), Err(GameError::InvalidKey(*hash)));
+    }
+    assert!(matches!(PositionKey::decode(0, (16, 4, 4)), Err(GameError::InvalidDimensions(16, 4))));
+
+    // neither stone can be part of a line any more, whether shaded or not
+    let rules = (4, 1, 4);
+    let live = ConnectFour::replicate_game_with_rules("------\nx\n\n\n\n------", 4, 1, 4).unwrap();
+    let dead = ConnectFour::replicate_game_with_rules("------\nx\no\n\n\n------", 4, 1, 4).unwrap();
+    let shaded = ConnectFour::replicate_game_with_rules("------\n:\n:\n\n\n------", 4, 1, 4).unwrap();
+    assert_ne!(PositionKey::with_shading(&dead, Shading::Keep), PositionKey::with_shading(&shaded, Shading::Keep));
+    assert_eq!(PositionKey::with_shading(&dead, Shading::Ignore), PositionKey::with_shading(&shaded, Shading::Ignore));
+    assert_eq!(PositionKey::with_shading(&live, Shading::Ignore), PositionKey::of(&live));
+    let (key, _) = PositionKey::with_shading(&dead, Shading::Ignore).unwrap();
+    assert_eq!(key.game(rules).unwrap().display(), "------\n\n\n:\n:\n------");
+}
+
+#[test]
+fn test_opening_book() {
+    // connect three on a 3x3 board is searched to the end in no time
+    let rules = (3, 3, 3);
+    let strategy = ConnectFourStrategy::default();
+    let book = OpeningBook::generate(rules, 2, &strategy, 9).unwrap();
+    // the empty board, two first moves and the replies to them that do not lose at once
+    assert!(book.len() > 3, "{} positions", book.len());
+
+    let game = ConnectFour::with_rules(3, 3, 3).unwrap();
+    let (column, score) = book.probe(&game, &Player::White).unwrap();
+    let g = Arc::new(RwLock::new(game.clone()));
+    assert_eq!(strategy.find_best_move(g, &Player::White, 9, false).1, Some(score));
+    assert!(column.to_usize() < 3);
+
+    // mirror images share an entry, the move is mirrored as well
+    let mut left = game.clone();
+    left.drop_stone(&Player::White, Column::One).unwrap();
+    let mut right = game.clone();
+    right.drop_stone(&Player::White, Column::Three).unwrap();
+    let (left_column, left_score) = book.probe(&left, &Player::Black).unwrap();
+    let (right_column, right_score) = book.probe(&right, &Player::Black).unwrap();
+    assert_eq!(left_column.to_usize() + right_column.to_usize(), 2);
+    assert_eq!(left_score, right_score);
+
+    // only the positions of the first plies and the rules of the book
+    let mut deep = right.clone();
+    deep.drop_stone(&Player::Black, Column::Two).unwrap();
+    deep.drop_stone(&Player::White, Column::Two).unwrap();
+    assert_eq!(book.probe(&deep, &Player::Black), None);
+    assert_eq!(book.probe(&ConnectFour::with_rules(3, 3, 2).unwrap(), &Player::White), None);
+
+    // round trip
+    let path = std::env::temp_dir().join(format!("connectfour-book-{}", std::process::id()));
+    book.write(&path).unwrap();
+    let read = OpeningBook::read(&path, rules).unwrap();
+    assert_eq!(read.len(), book.len());
+    assert_eq!(read.probe(&right, &Player::Black), Some((right_column, right_score)));
+    assert_eq!(OpeningBook::read(&path, (3, 3, 2)).err().unwrap().kind(), std::io::ErrorKind::InvalidData);
+    std::fs::remove_file(&path).unwrap();
+
+    // the book move comes first, the search only for what is not in the book
+    let book_strategy = BookStrategy::new(Arc::new(book), ConnectFourStrategy::default());
+    let g = Arc::new(RwLock::new(left.clone()));
+    let (mv, score) = book_strategy.find_best_move_in_time(g, &Player::Black, Duration::from_secs(60), false);
+    assert_eq!((mv.unwrap().data().clone(), score.unwrap()), (left_column, left_score));
+    let g = Arc::new(RwLock::new(deep.clone()));
+    assert_eq!(book_strategy.find_best_move(g.clone(), &Player::Black, 9, false).1,
+               strategy.find_best_move(g, &Player::Black, 9, false).1);
+
+    // the heuristic strategy plays a book it is given, whatever it makes of the position
+    let path = std::env::temp_dir().join(format!("connectfour-book-corner-{}", std::process::id()));
+    let corner = StoredGame { hash: 0, player: Player::White, score: Score::Won(40), column: Some(Column::Three) };
+    write_store(&path, rules, &[corner]).unwrap();
+    let corner = Arc::new(OpeningBook::read(&path, rules).unwrap());
+    std::fs::remove_file(&path).unwrap();
+    let strategy = ConnectFourStrategy { book: Some(corner), ..ConnectFourStrategy::default() };
+    let g = Arc::new(RwLock::new(game.clone()));
+    let (mv, score) = strategy.find_best_move(g.clone(), &Player::White, 2, false);
+    assert_eq!((mv.unwrap().data().clone(), score.unwrap()), (Column::Three, Score::Won(40)));
+    let (mv, _) = strategy.find_best_move_in_time(g.clone(), &Player::White, Duration::from_secs(1), false);
+    assert_eq!(mv.unwrap().data(), &Column::Three);
+    assert_eq!(strategy.analyze(g, &Player::White, 2, false).variation.first(), Some(&Column::Three));
+}
+
+#[test]
+fn test_endgame_table() {
+    let rules = (3, 3, 3);
+    let table = EndgameTable::generate(rules, 0).unwrap();
+    let kind = |score: &Score| match score {
+        Score::Won(_) => "won",
+        Score::Lost(_) => "lost",
+        Score::Remis(_) => "remis",
+        Score::Undecided(_) => "undecided",
+    };
+
+    // as good as a search to the end, for either player to move first
+    let strategy = ConnectFourStrategy::default();
+    let game = ConnectFour::with_rules(3, 3, 3).unwrap();
+    for p in &[Player::White, Player::Black] {
+        let (_, score) = table.probe(&game, p).unwrap();
+        let g = Arc::new(RwLock::new(game.clone()));
+        assert_eq!(kind(&strategy.find_best_move(g, p, 9, false).1.unwrap()), kind(&score));
+    }
+    let mut left = game.clone();
+    left.drop_stone(&Player::White, Column::One).unwrap();
+    let mut right = game.clone();
+    right.drop_stone(&Player::White, Column::Three).unwrap();
+    let (left_column, left_score) = table.probe(&left, &Player::Black).unwrap();
+    let (right_column, right_score) = table.probe(&right, &Player::Black).unwrap();
+    assert_eq!(left_column.to_usize() + right_column.to_usize(), 2);
+    assert_eq!(left_score, right_score);
+    let g = Arc::new(RwLock::new(left.clone()));
+    assert_eq!(kind(&strategy.find_best_move(g, &Player::Black, 9, false).1.unwrap()), kind(&left_score));
+    // not the player's turn
+    assert_eq!(table.probe(&left, &Player::White), None);
+
+    // round trip
+    let path = std::env::temp_dir().join(format!("connectfour-endgame-{}", std::process::id()));
+    table.write(&path).unwrap();
+    let read = EndgameTable::read(&path, rules).unwrap();
+    assert_eq!(read.len(), table.len());
+    assert_eq!(read.probe(&right, &Player::Black), Some((right_column.clone(), right_score.clone())));
+    std::fs::remove_file(&path).unwrap();
+
+    // the brute force search looks up the games late enough for the table
+    let late = EndgameTable::generate(rules, 4).unwrap();
+    assert_eq!(late.probe(&left, &Player::Black), None);
+    let verdict = |endgame: Option<Arc<EndgameTable>>| {
+        let strategy = BruteForceStrategy::new(BruteForceConfig { dump: None, endgame, ..BruteForceConfig::default() });
+        let (mv, score) = strategy.find_best_move(Arc::new(RwLock::new(right.clone())), &Player::Black, 0, true);
+        (mv.unwrap().data().clone(), kind(&score.unwrap()))
+    };
+    assert_eq!(verdict(Some(Arc::new(late))).1, kind(&right_score));
+    assert_eq!(verdict(Some(Arc::new(table))), (right_column.clone(), kind(&right_score)));
+
+    // the positions are kept shaded, a game with dead stones is found without shading it first
+    let wide = (4, 3, 3);
+    let mut opening = ConnectFour::with_rules(4, 3, 3).unwrap();
+    opening.play_move_string("23").unwrap();
+    let shaded_table = EndgameTable::generate_from(&opening, &Player::White, 6).unwrap();
+    let mut dead = ConnectFour::with_rules(4, 3, 3).unwrap();
+    dead.play_move_string("231112").unwrap();
+    assert_eq!(shaded_table.lookup(PositionKey::of(&dead).unwrap().0, &Player::White), None);
+    assert!(shaded_table.lookup_game(&dead, &Player::White).is_some());
+    // a table that has every mover lose in the positions with gray stones makes the search believe it,
+    // the workers shade their games the same way
+    let path = std::env::temp_dir().join(format!("connectfour-endgame-forged-{}", std::process::id()));
+    shaded_table.write(&path).unwrap();
+    let shaded = |hash: i128| PositionKey::decode(hash, wide).unwrap().state().iter()
+        .any(|column| column.contains(&Some(Player::Gray)));
+    let forged: Vec<StoredGame> = read_store(&path, wide).unwrap().into_iter()
+        .map(|game| if shaded(game.hash) { StoredGame { score: Score::Lost(0), ..game } } else { game })
+        .collect();
+    write_store(&path, wide, &forged).unwrap();
+    let forged = EndgameTable::read(&path, wide).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let wide_verdict = |endgame: Option<Arc<EndgameTable>>| {
+        let strategy = BruteForceStrategy::new(BruteForceConfig { dump: None, endgame, ..BruteForceConfig::default() });
+        let (_, score) = strategy.find_best_move(Arc::new(RwLock::new(opening.clone())), &Player::White, 0, true);
+        kind(&score.unwrap())
+    };
+    assert_eq!(wide_verdict(Some(Arc::new(shaded_table))), "won");
+    assert_eq!(wide_verdict(Some(Arc::new(forged))), "lost");
+
+    // only the positions the game may still come to
+    let reachable = EndgameTable::generate_from(&right, &Player::Black, 0).unwrap();
+    assert_eq!(reachable.probe(&right, &Player::Black), Some((right_column.clone(), right_score.clone())));
+    assert_eq!(reachable.probe(&game, &Player::White), None);
+    let mut other = game.clone();
+    other.drop_stone(&Player::White, Column::Two).unwrap();
+    assert_eq!(reachable.probe(&other, &Player::Black), None);
+    // pops take stones off the board, the walk would not end
+    let mut popping = game.clone();
+    popping.set_pop_out(true);
+    assert!(matches!(EndgameTable::generate_from(&popping, &Player::White, 0), Err(GameError::UnsupportedRules)));
+
+    // the heuristic strategy plays the table's moves without looking ahead
+    let strategy = ConnectFourStrategy { endgame: Some(Arc::new(reachable)), ..ConnectFourStrategy::default() };
+    let (mv, score) = strategy.find_best_move(Arc::new(RwLock::new(right.clone())), &Player::Black, 0, true);
+    assert_eq!((mv.unwrap().data().clone(), score.unwrap()), (right_column.clone(), right_score.clone()));
+    let (mv, _) = strategy.find_best_move_in_time(Arc::new(RwLock::new(right.clone())), &Player::Black, Duration::from_secs(1), true);
+    assert_eq!(mv.unwrap().data(), &right_column);
+}
+
+#[test]
+fn test_mcts() {
+    let strategy = MctsStrategy::new(MctsConfig { playouts: 2000, ..MctsConfig::default() });
+    let mut game = ConnectFour::new();
+    for _ in 0..3 {
+        game.drop_stone(&Player::White, Column::One).unwrap();
+    }
+    game.drop_stone(&Player::Black, Column::Four).unwrap();
+    game.drop_stone(&Player::Black, Column::Four).unwrap();
+
+    // a win at once
+    let g = Arc::new(RwLock::new(game.clone()));
+    let (mv, score) = strategy.find_best_move(g.clone(), &Player::White, 0, false);
+    assert_eq!(mv.unwrap().data(), &Column::One);
+    assert_eq!(score, Some(Score::Won(0)));
+    // the search leaves the game as it was
+    assert_eq!(g.read().unwrap().state(), game.state());
+
+    // the threat is blocked
+    let (mv, _) = strategy.find_best_move(g.clone(), &Player::Black, 0, false);
+    assert_eq!(mv.unwrap().data(), &Column::One);
+    let analysis = strategy.analyze_position(g.clone(), &Player::Black, 0, false);
+    assert_eq!(analysis.len(), 7);
+    assert_eq!(analysis, strategy.analyze_position(g.clone(), &Player::Black, 0, false));
+
+    // a move that is not possible
+    let mut full = ConnectFour::new();
+    for _ in 0..6 {
+        full.drop_stone(&Player::Gray, Column::Seven).unwrap();
+    }
+    let mv: Arc<dyn Move<Column>> = Arc::new(ConnectFourMove { data: Column::Seven });
+    assert!(strategy.evaluate_move(Arc::new(RwLock::new(full)), &Player::White, mv).is_err());
+
+    // as many playouts as fit into a time budget
+    let start = Instant::now();
+    let (mv, _) = strategy.find_best_move_in_time(g.clone(), &Player::Black, Duration::from_millis(200), false);
+    assert!(start.elapsed() < Duration::from_secs(2));
+    assert_eq!(mv.unwrap().data(), &Column::One);
+
+    // heuristic playouts are much slower
+    let strategy = MctsStrategy::new(MctsConfig { playouts: 100, heuristic_playouts: true, ..MctsConfig::default() });
+    let (mv, _) = strategy.find_best_move(g.clone(), &Player::Black, 0, false);
+    assert_eq!(mv.unwrap().data(), &Column::One);
 }
 
 #[test]
 fn test_basically_over() {
     let nworker = 1;
     let player = Player::Black;
+    let dumpfile = dump_file("basically_over");
+    let config = BruteForceConfig {
+        nworkers: nworker, dump: Some(dumpfile.clone()), ..BruteForceConfig::default()
+    };
 
-    let strategy = BruteForceStrategy::new(nworker);
-    
     let thirty_stones = "------
 :::xo
 :::ox
@@ -738,12 +1531,10 @@ fn test_basically_over() {
 :::xo
 ------";
 
-    unsafe {
-        BASICALLY_OVER = 36;
-    }
+    let strategy = BruteForceStrategy::new(BruteForceConfig { basically_over: 36, ..config.clone() });
 
-    let game = ConnectFour::replicate_game(thirty_stones);
-    let g = Rc::new(RefCell::new(game.clone()));
+    let game = ConnectFour::replicate_game(thirty_stones).unwrap();
+    let g = Arc::new(RwLock::new(game.clone()));
     let toplimit = 0;
 
     match strategy.find_best_move(g.clone(), &player, toplimit, true) {
@@ -753,7 +1544,7 @@ fn test_basically_over() {
             if let Score::Remis(n) = score { assert!(n == 6); }
             else { assert!(false); }
 
-            let dump = std::fs::read_to_string(STRDMP).unwrap();
+            let dump = std::fs::read_to_string(&dumpfile).unwrap();
             let expected = std::fs::read_to_string("tests/data/thirtyfivestones").unwrap();
             assert!(dump == expected,
                 std::fs::write("tests/data/thirtyfivestones~", dump).unwrap()
@@ -762,12 +1553,10 @@ fn test_basically_over() {
         _ => { assert!(false); },
     };
 
-    unsafe {
-        BASICALLY_OVER = 30;
-    }
+    let strategy = BruteForceStrategy::new(BruteForceConfig { basically_over: 30, ..config });
 
-    let game = ConnectFour::replicate_game(thirty_stones);
-    let g = Rc::new(RefCell::new(game.clone()));
+    let game = ConnectFour::replicate_game(thirty_stones).unwrap();
+    let g = Arc::new(RwLock::new(game.clone()));
     let toplimit = 0;
 
     match strategy.find_best_move(g.clone(), &player, toplimit, true) {
@@ -776,7 +1565,7 @@ fn test_basically_over() {
             if let Score::Remis(n) = score { assert!(n == 6); }
             else { assert!(false); }
 
-            let dump = std::fs::read_to_string(STRDMP).unwrap();
+            let dump = std::fs::read_to_string(&dumpfile).unwrap();
             let expected = std::fs::read_to_string("tests/data/thirtystones").unwrap();
             assert!(dump == expected,
                 std::fs::write("tests/data/thirtystones~", dump).unwrap()
@@ -785,3 +1574,554 @@ fn test_basically_over() {
         _ => { assert!(false); },
     };
 }
+
+#[test]
+fn test_bitboard() {
+    let plan = "------
+:::xo
+oxox
+x
+
+xo:x
+
+o
+------";
+    // lossless both ways
+    let game = ConnectFour::replicate_game(plan).unwrap();
+    let bb = BitBoard::try_from(&game).unwrap();
+    assert_eq!(bb.display(), plan);
+    assert_eq!(bb.dropped_stones(), game.dropped_stones());
+    assert_eq!(ConnectFour::from(&bb).display(), plan);
+    // other boards do not fit
+    assert!(matches!(BitBoard::try_from(&ConnectFour::with_dimensions(8, 7).unwrap()), Err(GameError::UnsupportedRules)));
+    assert!(matches!(BitBoard::try_from(&ConnectFour::with_rules(7, 6, 3).unwrap()), Err(GameError::UnsupportedRules)));
+
+    // same scores as the vector board on a long series of pseudo-random games
+    let mut seed:u64 = 4453;
+    for _ in 0..50 {
+        let mut cf = ConnectFour::new();
+        let mut bb = BitBoard::new();
+        let mut p = Player::White;
+        let mut dropped = Vec::new();
+        loop {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let col = Column::from_usize((seed >> 33) as usize % STANDARD_WIDTH).unwrap();
+            match (cf.drop_stone(&p, col.clone()), bb.drop_stone(&p, col.clone())) {
+                (Ok(a), Ok(b)) => {
+                    assert_eq!(a, b, "{}", cf.display());
+                    dropped.push((p.clone(), col));
+                    if let Score::Undecided(_) = a {} else { break; }
+                },
+                (Err(_), Err(_)) => continue,
+                _ => panic!("boards disagree\n{}", cf.display()),
+            }
+            assert_eq!(bb, BitBoard::try_from(&cf).unwrap());
+            p = p.opponent().clone();
+        }
+        // and back to the empty board
+        while let Some((p, col)) = dropped.pop() {
+            bb.undrop_stone(&p, col).unwrap();
+        }
+        assert_eq!(bb, BitBoard::new());
+    }
+}
+
+#[test]
+fn test_solver() {
+    let solver = SolverStrategy::default();
+    let strategy = ConnectFourStrategy {
+        mscore_koeff: 1.0,
+        oscore_koeff: 0.8,
+        nscore_koeff: 0.5,
+        my_tabu_koeff: 0.0,
+        opp_tabu_koeff: 0.0,
+        tabu_defense_koeff: 0.0,
+        nthreads: 1,
+        book: None,
+        endgame: None,
+    };
+
+    // wherever the heuristic search finds a decision, the solver has to agree
+    let positions = [
+        ("------\n\n\n\nox\nox\n\n\n------", Player::White, 4, Column::Three, Score::Won(2)),
+        ("------\n\nxx\nxoxo\noxooxo\nxxoo\nox\n\n------", Player::White, 3, Column::Six, Score::Won(2)),
+    ];
+    for (plan, player, moves_ahead, column, score) in positions.iter() {
+        let game = ConnectFour::replicate_game(plan).unwrap();
+        let g = Arc::new(RwLock::new(game.clone()));
+        match (solver.find_best_move(g.clone(), player, 0, false),
+               strategy.find_best_move(g.clone(), player, *moves_ahead, true)) {
+            ((Some(mv), Some(exact)), (Some(_), Some(heuristic))) => {
+                println!("{:?} {:?} {:?}", mv.data(), exact, heuristic);
+                assert_eq!(exact, heuristic);
+                assert_eq!(exact, *score);
+                assert_eq!(*mv.data(), *column);
+            },
+            _ => panic!("no move for\n{}", plan),
+        }
+    }
+
+    // every column, wins counted in stones dropped after the move
+    let game = ConnectFour::replicate_game(positions[1].0).unwrap();
+    assert_eq!(solver.solve_moves(&BitBoard::try_from(&game).unwrap(), &Player::White), vec![
+        (Column::One, Score::Won(6)),
+        (Column::Two, Score::Lost(1)),
+        (Column::Three, Score::Won(6)),
+        (Column::Five, Score::Won(4)),
+        (Column::Six, Score::Won(2)),
+        (Column::Seven, Score::Lost(1)),
+    ]);
+
+    // a draw is reached when the field is full
+    let game = ConnectFour::replicate_game("------
+:::xo
+:::ox
+:::xo
+:::ox
+:::xo
+:::ox
+:::xo
+------").unwrap();
+    match solver.find_best_move(Arc::new(RwLock::new(game)), &Player::Black, 0, false) {
+        (Some(_), Some(score)) => assert_eq!(score, Score::Remis(6)),
+        _ => panic!("no move"),
+    }
+
+    // nothing to solve once a line is connected
+    for plan in ["------\nxxx\nox\nxx\nxxo\n\n\n\n------", "------\nxxo\nxx\nox\nxxx\n\n\n\n------"] {
+        let won = ConnectFour::replicate_game(plan).unwrap();
+        let bb = BitBoard::try_from(&won).unwrap();
+        assert!(matches!(solver.solve(&bb, &Player::White), Err(GameError::GameOver)));
+        assert!(solver.solve_moves(&bb, &Player::White).is_empty());
+        let g = Arc::new(RwLock::new(won));
+        assert_eq!(solver.find_best_move(g.clone(), &Player::White, 0, false).1, None);
+        let mv: Arc<dyn Move<Column>> = Arc::new(ConnectFourMove { data: Column::Five });
+        assert!(matches!(solver.evaluate_move(g, &Player::White, mv), Err(GameError::GameOver)));
+    }
+
+    // other boards are no business of the solver
+    let g = Arc::new(RwLock::new(ConnectFour::with_dimensions(8, 7).unwrap()));
+    assert!(solver.find_best_move(g.clone(), &Player::White, 0, false).0.is_none());
+    assert!(solver.analyze_position(g.clone(), &Player::White, 0, false).is_empty());
+    let mv: Arc<dyn Move<Column>> = Arc::new(ConnectFourMove { data: Column::One });
+    assert!(matches!(solver.evaluate_move(g, &Player::White, mv), Err(GameError::UnsupportedRules)));
+}
+
+#[test]
+fn test_find_best_move_in_time() {
+    let strategy = ConnectFourStrategy::default();
+    let plan = "------
+ox
+ooxo
+x
+oxooox
+xxoxxx
+
+oxo
+------";
+    let g = Arc::new(RwLock::new(ConnectFour::replicate_game(plan).unwrap()));
+
+    // even without any time there is a move
+    match strategy.find_best_move_in_time(g.clone(), &Player::White, Duration::from_millis(0), true) {
+        (Some(_), Some(Score::Undecided(_))) => (),
+        _ => panic!("no move"),
+    }
+
+    // the deadline is kept and the game is left as it was
+    let budget = Duration::from_millis(300);
+    let then = Instant::now();
+    match strategy.find_best_move_in_time(g.clone(), &Player::White, budget, true) {
+        (Some(mv), Some(score)) => println!("{:?} {:?}", mv.data(), score),
+        _ => panic!("no move"),
+    }
+    let took = Instant::now().duration_since(then);
+    assert!(took < budget + Duration::from_millis(500), "took {:?}", took);
+    assert_eq!(g.read().unwrap().display(), plan);
+
+    // a decision ends the search early
+    let mut game = ConnectFour::new();
+    game.drop_stone(&Player::White, Column::Four).unwrap();
+    game.drop_stone(&Player::Black, Column::Four).unwrap();
+    game.drop_stone(&Player::White, Column::Five).unwrap();
+    game.drop_stone(&Player::Black, Column::Five).unwrap();
+    let then = Instant::now();
+    match strategy.find_best_move_in_time(Arc::new(RwLock::new(game)), &Player::White, Duration::from_secs(60), true) {
+        (Some(mv), Some(score)) => {
+            assert_eq!(*mv.data(), Column::Three);
+            assert_eq!(score, Score::Won(2));
+        },
+        _ => panic!("no move"),
+    }
+    assert!(Instant::now().duration_since(then) < Duration::from_secs(30));
+
+    // no move on a full field
+    let full = ConnectFour::with_rules(3, 2, 3).unwrap();
+    let g = Arc::new(RwLock::new(full));
+    for column in [Column::One, Column::Two, Column::Three, Column::One, Column::Two, Column::Three].iter() {
+        g.write().unwrap().make_move(&Player::Gray, Arc::new(ConnectFourMove { data: column.clone() })).unwrap();
+    }
+    assert!(matches!(strategy.find_best_move_in_time(g.clone(), &Player::White, Duration::from_secs(1), true), (None, None)));
+    assert!(matches!(strategy.find_best_move(g, &Player::White, 2, true), (None, None)));
+
+    // nor does a draw, the budget is not used up
+    let mut drawn = ConnectFour::with_rules(3, 2, 3).unwrap();
+    drawn.play_move_string("1231").unwrap();
+    let then = Instant::now();
+    let (mv, score) = strategy.find_best_move_in_time(Arc::new(RwLock::new(drawn)), &Player::White, Duration::from_secs(20), true);
+    assert!(mv.is_some());
+    assert!(matches!(score, Some(Score::Remis(_))));
+    assert!(then.elapsed() < Duration::from_secs(5), "took {:?}", then.elapsed());
+
+    // strategies with searches of their own use them
+    let g = Arc::new(RwLock::new(ConnectFour::replicate_game(plan).unwrap()));
+    let solver = SolverStrategy::default();
+    assert_eq!(solver.find_best_move_in_time(g.clone(), &Player::White, Duration::from_millis(0), false).1,
+               solver.find_best_move(g.clone(), &Player::White, 0, false).1);
+    let brute_force = BruteForceStrategy::new(BruteForceConfig { dump: None, ..BruteForceConfig::default() });
+    let then = Instant::now();
+    assert!(brute_force.find_best_move_in_time(g.clone(), &Player::White, budget, false).0.is_some());
+    assert!(then.elapsed() < budget + Duration::from_secs(2), "took {:?}", then.elapsed());
+    assert_eq!(g.read().unwrap().display(), plan);
+}
+
+#[test]
+fn test_analyze() {
+    let strategy = ConnectFourStrategy::default();
+    let mut game = ConnectFour::new();
+    game.drop_stone(&Player::White, Column::Four).unwrap();
+    game.drop_stone(&Player::Black, Column::Four).unwrap();
+    game.drop_stone(&Player::White, Column::Five).unwrap();
+    game.drop_stone(&Player::Black, Column::Five).unwrap();
+    let plan = game.display();
+    let g = Arc::new(RwLock::new(game));
+
+    let analysis = strategy.analyze(g.clone(), &Player::White, 4, true);
+    for (column, score) in analysis.scores.iter() {
+        println!("{:?} {:?}", column, score);
+    }
+    assert_eq!(analysis.scores.len(), 7);
+    assert_eq!(analysis.scores[2], (Column::Three, Score::Won(2)));
+    assert_eq!(analysis.scores[5], (Column::Six, Score::Won(2)));
+
+    // the best move, any reply of the doomed opponent and the winning move
+    println!("{:?}", analysis.variation);
+    assert_eq!(analysis.variation.len(), 3);
+    assert_eq!(analysis.variation[0], Column::Three);
+    assert_eq!(g.read().unwrap().display(), plan);
+    let mut game = ConnectFour::replicate_game(&plan).unwrap();
+    game.drop_stone(&Player::White, analysis.variation[0].clone()).unwrap();
+    game.drop_stone(&Player::Black, analysis.variation[1].clone()).unwrap();
+    assert_eq!(game.drop_stone(&Player::White, analysis.variation[2].clone()).unwrap(), Score::Won(0));
+}
+
+#[test]
+fn test_analyze_brute_force() {
+    let strategy = BruteForceStrategy::new(BruteForceConfig { dump: None, ..BruteForceConfig::default() });
+    let mut game = ConnectFour::with_rules(4, 4, 3).unwrap();
+    game.play_move_string("1212").unwrap();
+    let plan = game.display();
+    let g = Arc::new(RwLock::new(game));
+
+    // a win at once is scored like the other strategies do
+    let analysis = strategy.analyze(g.clone(), &Player::White, 0, false);
+    assert_eq!(analysis.scores.len(), 4);
+    assert_eq!(analysis.scores[0], (Column::One, Score::Won(0)));
+    assert_eq!(analysis.variation, vec![Column::One]);
+    // the player not on turn is analyzed as well
+    let scores = strategy.analyze_position(g.clone(), &Player::Black, 0, false);
+    assert_eq!(scores.len(), 4);
+    assert_eq!(scores[1], (Column::Two, Score::Won(0)));
+    assert_eq!(g.read().unwrap().display(), plan);
+}
+
+#[test]
+fn test_analyze_position() {
+    let game = ConnectFour::replicate_game("------\n\nxx\nxoxo\noxooxo\nxxoo\nox\n\n------").unwrap();
+    let g = Arc::new(RwLock::new(game));
+
+    // every column that is not full, in column order
+    let exact = SolverStrategy::default().analyze_position(g.clone(), &Player::White, 0, false);
+    assert_eq!(exact, vec![
+        (Column::One, Score::Won(6)),
+        (Column::Two, Score::Lost(1)),
+        (Column::Three, Score::Won(6)),
+        (Column::Five, Score::Won(4)),
+        (Column::Six, Score::Won(2)),
+        (Column::Seven, Score::Lost(1)),
+    ]);
+
+    // wherever the heuristic search finds a decision, the solver has to agree
+    let heuristic = ConnectFourStrategy::default().analyze_position(g.clone(), &Player::White, 3, true);
+    assert_eq!(heuristic.len(), exact.len());
+    for ((column, score), (exact_column, exact_score)) in heuristic.iter().zip(exact.iter()) {
+        println!("{:?} {:?} {:?}", column, score, exact_score);
+        assert_eq!(column, exact_column);
+        if let Score::Undecided(_) = score { continue; }
+        assert_eq!(score, exact_score);
+    }
+    assert_eq!(heuristic[4], (Column::Six, Score::Won(2)));
+}
+
+#[test]
+fn test_threads() {
+    let strategy = Arc::new(ConnectFourStrategy::default());
+    let game = ConnectFour::replicate_game("------
+ox
+ooxo
+x
+oxooox
+xxoxxx
+
+oxo
+------").unwrap();
+    let serial = match strategy.find_best_move(Arc::new(RwLock::new(game.clone())), &Player::White, 3, true) {
+        (Some(mv), Some(score)) => (mv.data().clone(), score),
+        _ => panic!("no move"),
+    };
+
+    // one strategy for all threads, each thread with a game of its own
+    let handles: Vec<_> = (0..4).map(|_| {
+        let strategy = Arc::clone(&strategy);
+        let game = game.clone();
+        thread::spawn(move || {
+            match strategy.find_best_move(Arc::new(RwLock::new(game)), &Player::White, 3, true) {
+                (Some(mv), Some(score)) => (mv.data().clone(), score),
+                _ => panic!("no move"),
+            }
+        })
+    }).collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap(), serial);
+    }
+}
+
+#[test]
+fn test_parallel_search() {
+    let serial = ConnectFourStrategy::default();
+    let parallel = ConnectFourStrategy::new(4);
+    let plans = ["------
+ox
+ooxo
+x
+oxooox
+xxoxxx
+
+oxo
+------", "------
+
+xx
+xoxo
+oxooxo
+xxoo
+ox
+
+------", "------
+x
+o
+xo
+xoxo
+x
+
+
+------"];
+    for plan in plans.iter() {
+        for player in [Player::Black, Player::White].iter() {
+            for moves_ahead in 0..4 {
+                let game = ConnectFour::replicate_game(plan).unwrap();
+                let g = Arc::new(RwLock::new(game.clone()));
+                let expected = match serial.find_best_move(g.clone(), player, moves_ahead, true) {
+                    (Some(mv), score) => (mv.data().clone(), score),
+                    _ => panic!("no move"),
+                };
+                match parallel.find_best_move(g.clone(), player, moves_ahead, true) {
+                    (Some(mv), score) => assert_eq!((mv.data().clone(), score), expected),
+                    _ => panic!("no move"),
+                }
+                assert_eq!(g.read().unwrap().display(), game.display());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_store() {
+    let path = std::env::temp_dir().join(format!("connectfour-store-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    // round trip
+    let games = vec![
+        StoredGame { hash: 2305843421530558464, player: Player::Black, score: Score::Won(2), column: Some(Column::Five) },
+        StoredGame { hash: -1, player: Player::White, score: Score::Remis(0), column: None },
+        StoredGame { hash: 209874779512449794048, player: Player::White, score: Score::Lost(7), column: Some(Column::One) },
+    ];
+    write_store(&path, (7, 6, 4), &games).unwrap();
+    assert_eq!(read_store(&path, (7, 6, 4)).unwrap(), games);
+    // so is a store for other rules
+    assert_eq!(read_store(&path, (7, 6, 5)).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+    // another version is rejected
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes[4] += 1;
+    std::fs::write(&path, &bytes).unwrap();
+    assert_eq!(read_store(&path, (7, 6, 4)).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    std::fs::remove_file(&path).unwrap();
+
+    // decided games are saved ...
+    let game = ConnectFour::replicate_game("------
+
+o
+
+xo
+
+x
+
+------").unwrap();
+    let (hash, _) = hash_from_state(game.state(), STANDARD_HEIGHT);
+    let mut strategy = BruteForceStrategy::new(BruteForceConfig { dump: None, ..BruteForceConfig::default() });
+    strategy.store = Some(String::from(path.to_str().unwrap()));
+    let verdict = match strategy.find_best_move(Arc::new(RwLock::new(game.clone())), &Player::Black, 0, true) {
+        (Some(mv), score) => (mv.data().clone(), score),
+        _ => panic!("no move"),
+    };
+    assert_eq!(verdict, (Column::Five, Some(Score::Won(2))));
+    let games = read_store(&path, (7, 6, 4)).unwrap();
+    assert!(games.iter().all(|g| g.player == Player::Black));
+    assert!(games.contains(&StoredGame { hash, player: Player::Black, score: Score::Won(2), column: Some(Column::Five) }));
+
+    // ... and known in the next run
+    let mut strategy = BruteForceStrategy::new(BruteForceConfig { dump: None, ..BruteForceConfig::default() });
+    strategy.store = Some(String::from(path.to_str().unwrap()));
+    match strategy.find_best_move(Arc::new(RwLock::new(game)), &Player::Black, 0, true) {
+        (Some(mv), score) => assert_eq!((mv.data().clone(), score), verdict),
+        _ => panic!("no move"),
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_checkpoint() {
+    let path = std::env::temp_dir().join(format!("connectfour-checkpoint-{}", std::process::id()));
+    let game = ConnectFour::replicate_game("------
+
+o
+
+xo
+
+x
+
+------").unwrap();
+    let (principal, _) = hash_from_state(game.state(), STANDARD_HEIGHT);
+
+    // round trip
+    let mut records = std::collections::HashMap::new();
+    records.insert(principal, GameState::Undecided);
+    records.insert(1, GameState::Decided(Score::Lost(3), Some(Column::Two)));
+    records.insert(2, GameState::Decided(Score::Remis(0), None));
+    records.insert(3, GameState::Novel);
+    records.insert(4, GameState::Recall);
+    let mut interests = std::collections::HashMap::new();
+    interests.insert(3, vec![principal]);
+    interests.insert(4, vec![principal, 4]);
+    let checkpoint = Checkpoint { principal, player: Player::Black, moves_ahead: 2, rules: (7, 6, 4), records, interests };
+    write_checkpoint(&path, &checkpoint).unwrap();
+    assert_eq!(read_checkpoint(&path).unwrap(), checkpoint);
+
+    // resume a search that has just started
+    let mut records = std::collections::HashMap::new();
+    records.insert(principal, GameState::Novel);
+    write_checkpoint(&path, &Checkpoint {
+        principal, player: Player::Black, moves_ahead: 0, rules: (7, 6, 4), records, interests: std::collections::HashMap::new(),
+    }).unwrap();
+    let mut strategy = BruteForceStrategy::new(BruteForceConfig { dump: None, ..BruteForceConfig::default() });
+    strategy.checkpoint = Some(String::from(path.to_str().unwrap()));
+    match strategy.find_best_move(Arc::new(RwLock::new(game.clone())), &Player::Black, 0, true) {
+        (Some(mv), Some(score)) => {
+            assert_eq!(*mv.data(), Column::Five);
+            assert_eq!(score, Score::Won(2));
+        },
+        _ => panic!("no move"),
+    }
+    // the search is done, there is nothing to resume
+    assert!(!path.exists());
+
+    // a checkpoint of a search to another depth is not resumed
+    let mut records = std::collections::HashMap::new();
+    records.insert(principal, GameState::Decided(Score::Lost(1), Some(Column::One)));
+    write_checkpoint(&path, &Checkpoint {
+        principal, player: Player::Black, moves_ahead: 2, rules: (7, 6, 4), records, interests: std::collections::HashMap::new(),
+    }).unwrap();
+    match strategy.find_best_move(Arc::new(RwLock::new(game)), &Player::Black, 0, true) {
+        (Some(mv), Some(score)) => {
+            assert_eq!(*mv.data(), Column::Five);
+            assert_eq!(score, Score::Won(2));
+        },
+        _ => panic!("no move"),
+    }
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_memory_limit() {
+    // without a limit the game store grows to thousands of records
+    let game = ConnectFour::replicate_game("------
+
+
+oxox
+
+oxox
+xo:x
+
+------").unwrap();
+    let mut strategy = BruteForceStrategy::new(BruteForceConfig { dump: None, ..BruteForceConfig::default() });
+    strategy.memory_limit = Some(200 * RECORD_MEMORY);
+    match strategy.find_best_move(Arc::new(RwLock::new(game)), &Player::Black, 0, true) {
+        (Some(mv), Some(score)) => {
+            assert_eq!(*mv.data(), Column::Four);
+            assert_eq!(score, Score::Won(4));
+        },
+        _ => panic!("no move"),
+    }
+}
+
+#[test]
+fn test_cancellation() {
+    let reports = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let collected = reports.clone();
+    let cancellation = CancellationToken::new();
+    let mut strategy = BruteForceStrategy::new(BruteForceConfig {
+        nworkers: 2, dump: None, progress_interval: Some(1), ..BruteForceConfig::default()
+    });
+    strategy.cancellation = Some(cancellation.clone());
+    strategy.progress = Some(Arc::new(move |progress: &Progress| {
+        collected.lock().unwrap().push(progress.clone());
+    }));
+
+    // far too early in the game to be decided any time soon
+    let game = ConnectFour::replicate_game("------
+
+
+
+xo
+
+
+
+------").unwrap();
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(500));
+        cancellation.cancel();
+    });
+    let then = Instant::now();
+    match strategy.find_best_move(Arc::new(RwLock::new(game)), &Player::Black, 0, true) {
+        (Some(_), Some(Score::Undecided(_))) => (),
+        verdict => panic!("unexpected verdict {:?}", verdict.1),
+    }
+    assert!(then.elapsed() < Duration::from_secs(10), "took {:?}", then.elapsed());
+    canceller.join().unwrap();
+
+    let reports = reports.lock().unwrap();
+    assert!(!reports.is_empty());
+    for (n, progress) in reports.iter().enumerate() {
+        assert_eq!(progress.jobs, n as u64 + 1);
+        assert!(progress.records > 0);
+    }
+}
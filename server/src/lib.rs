@@ -10,9 +10,7 @@ use iron::status;
 
 use iron::Handler;
 
-use std::sync::Mutex;
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::sync::{Arc,Mutex,RwLock};
 use std::collections::HashMap;
 use std::time::{Instant, Duration};
 use std::thread::sleep;
@@ -20,15 +18,62 @@ use std::thread::sleep;
 struct ConnectFourHandler {
     zero: Instant,
     cfm: Mutex<HashMap<u128,ConnectFour>>,
-    lam: Mutex<HashMap<u128,i32>>,
     st: ConnectFourStrategy,
 }
 
 use iron::headers::AccessControlAllowOrigin;
 
-const STARTN:i32 = 6;
-const RESPITE:u128 = 400;
-const TOLERABLE:u128 = 2800;
+// time for finding the best move in milliseconds, the search looks ahead as far as it gets
+const TOLERABLE:u64 = 2800;
+// how far the analysis of every column looks ahead
+const ANALYSIS_DEPTH:i32 = 4;
+
+// the strategies try out moves of both players, they need a game that does not insist on turns
+fn analysis_copy(cfg: &ConnectFour) -> ConnectFour {
+    let mut copy = cfg.clone();
+    copy.set_permissive(true);
+    copy
+}
+
+// lines of (column, row) cells as JSON arrays
+fn lines_json(lines: &[Vec<(usize,usize)>]) -> String {
+    let lines:Vec<String> = lines.iter().map(|line| {
+        let cells:Vec<String> = line.iter().map(|(n, m)| format!("[{}, {}]", n, m)).collect();
+        format!("[{}]", cells.join(", "))
+    }).collect();
+    format!("[{}]", lines.join(", "))
+}
+
+// the moves of a finished game in digit notation, as another JSON member if they can be written that way
+fn moves_json(cfg: &ConnectFour) -> String {
+    match cfg.move_string() {
+        Some(moves) => format!(", \"moves\": \"{}\"", moves),
+        None => String::new(),
+    }
+}
+
+// moves that are not possible at all are bad requests, moves at the wrong time conflict with the game
+fn error_status(e: &GameError) -> status::Status {
+    match e {
+        GameError::GameOver | GameError::WrongTurn | GameError::NotLastMove => status::Conflict,
+        _ => status::BadRequest,
+    }
+}
+
+fn unknown_game(gameid: u128) -> (status::Status, String) {
+    (status::NotFound, format!("there is no game {}", gameid))
+}
+
+// a column and what it leads to, as JSON
+fn column_score(column: &Column, score: &Score) -> String {
+    let (kind, value) = match score {
+        Score::Won(in_n) => ("won", in_n.to_string()),
+        Score::Lost(in_n) => ("lost", in_n.to_string()),
+        Score::Remis(in_n) => ("remis", in_n.to_string()),
+        Score::Undecided(evaluation) => ("undecided", evaluation.to_string()),
+    };
+    format!("{{ \"column\": {}, \"score\": \"{}\", \"value\": {} }}", column.to_usize(), kind, value)
+}
 
 impl Handler for ConnectFourHandler {
     fn handle(&self, req: &mut Request) -> IronResult<Response> {
@@ -49,16 +94,9 @@ impl Handler for ConnectFourHandler {
             }
             let mut column = None;
             if let Some(c) = &req.url.path().get(3) {
-                column = match **c {
-                    "0" => Some(Column::One),
-                    "1" => Some(Column::Two),
-                    "2" => Some(Column::Three),
-                    "3" => Some(Column::Four),
-                    "4" => Some(Column::Five),
-                    "5" => Some(Column::Six),
-                    "6" => Some(Column::Seven),
-                    _ => None,
-                };
+                if let Ok(n) = (**c).parse::<usize>() {
+                    column = Column::from_usize(n).ok();
+                }
             }
             (gameid, player, column)
         }
@@ -75,9 +113,12 @@ impl Handler for ConnectFourHandler {
         }
 
         let mut answer = None;
+        // what went wrong if there is no answer
+        let mut failure = (status::BadRequest, String::from("malformed request"));
 
         let mut evaluation_clone:Option<ConnectFour> = None;
         let mut best_move_clone:Option<ConnectFour> = None;
+        let mut analysis_clone:Option<ConnectFour> = None;
 
         let mut key = 0;
         if let Some(s) = &req.url.path().get(0) {
@@ -90,47 +131,85 @@ impl Handler for ConnectFourHandler {
                     answer = Some(String::from("{ \"date\": \"2018-01-28\" }"));
                 },
                 "new" => {
-                    key = key_from_time(&(*cfm), self.zero);
+                    // new/<width>/<height>[/<win length>] for another board than the standard one,
+                    // a trailing popout for the Pop Out variant and ?moves=4453 for the moves played so far
+                    let mut path = req.url.path();
+                    let pop_out = path.last() == Some(&"popout");
+                    if pop_out { path.pop(); }
+                    let numbers: Vec<Option<usize>> = path[1..].iter()
+                        .map(|n| (**n).parse::<usize>().ok())
+                        .collect();
+                    let rules = match numbers.as_slice() {
+                        [Some(width), Some(height)] => Some((*width, *height, STANDARD_WIN_LENGTH)),
+                        [Some(width), Some(height), Some(win_length)] => Some((*width, *height, *win_length)),
+                        [] => Some((STANDARD_WIDTH, STANDARD_HEIGHT, STANDARD_WIN_LENGTH)),
+                        _ => None,
+                    };
+                    let moves = req.url.query().and_then(|q| q.split('&').find_map(|kv| kv.strip_prefix("moves=")));
+                    if let Some((width, height, win_length)) = rules {
+                        let game = ConnectFour::with_rules(width, height, win_length).and_then(|mut cfg| {
+                            cfg.set_permissive(false);
+                            cfg.set_pop_out(pop_out);
+                            cfg.play_move_string(moves.unwrap_or(""))?;
+                            Ok(cfg)
+                        });
+                        match game {
+                            Ok(cfg) => {
+                                key = key_from_time(&(*cfm), self.zero);
 
-                    (*cfm).insert(key, ConnectFour::new());
-
-                    let mut lam = self.lam.lock().unwrap();
-                    (*lam).insert(key, STARTN);
-                    
-                    // answer must be proper JSON (", no ', \\n, no \n) for ajax
-                    answer = Some(format!("{{ \"field\": \"{}\", \"gameid\": {} }}", (*cfm).get(&key).unwrap().display().replace("\n", "\\n"), key));
+                                // answer must be proper JSON (", no ', \\n, no \n) for ajax
+                                answer = Some(format!("{{ \"field\": \"{}\", \"gameid\": {} }}", cfg.display().replace("\n", "\\n"), key));
+                                (*cfm).insert(key, cfg);
+                            },
+                            Err(e) => { failure = (error_status(&e), e.to_string()); },
+                        }
+                    }
                 },
-                "move" => {
+                "move" | "pop" => {
                     if let (Some(gameid), Some(player), Some(column)) = readurl(&req) {
-                        println!("{} {} {:?}", gameid, player, column);
-                        // move game out of map ...
-                        let mut cfg = (*cfm).remove(&gameid).unwrap();
-                        if let Ok(score) = cfg.drop_stone(&player, column) {
-                            answer = Some(format!("{{ \"field\": \"{}\" }}", cfg.display().replace("\n", "\\n")));
-                            match score {
-                                // forget about if it's over
-                                Score::Won(0) => (),
-                                Score::Remis(0) => (),
-                                _ => { (*cfm).insert(gameid, cfg); }
-                            }
-                        } else {
-                            // ... and in again for gaining ownership
-                            (*cfm).insert(gameid, cfg);
+                        println!("{} {} {} {:?}", gameid, s, player, column);
+                        let pop = **s == "pop";
+                        // the game refuses moves out of turn and after it is over
+                        match (*cfm).get_mut(&gameid) {
+                            Some(cfg) => match if pop { cfg.pop_stone(&player, column.clone()) } else { cfg.drop_stone(&player, column.clone()) } {
+                                Ok(score) => {
+                                    answer = Some(match score {
+                                        // the connected stones, for highlighting
+                                        Score::Won(_) | Score::Lost(_) => {
+                                            let winner = if let Score::Won(_) = score { &player } else { player.opponent() };
+                                            let lines = if pop { cfg.lines_in_column(&column, winner) } else { cfg.winning_lines(&column) };
+                                            format!("{{ \"field\": \"{}\", \"lines\": {}{} }}",
+                                                cfg.display().replace("\n", "\\n"), lines_json(&lines), moves_json(cfg))
+                                        },
+                                        Score::Remis(_) => format!("{{ \"field\": \"{}\"{} }}",
+                                            cfg.display().replace("\n", "\\n"), moves_json(cfg)),
+                                        _ => format!("{{ \"field\": \"{}\" }}", cfg.display().replace("\n", "\\n")),
+                                    });
+                                },
+                                Err(e) => { failure = (error_status(&e), e.to_string()); },
+                            },
+                            None => { failure = unknown_game(gameid); },
                         }
                     }
                 },
                 "withdraw" => {
                     if let (Some(gameid), Some(player), Some(column)) = readurl(&req) {
-                        let mut cfg = (*cfm).remove(&gameid).unwrap();
-                        cfg.withdraw_move(&player, Rc::new(ConnectFourMove{ data: column, }));
-                        answer = Some(format!("{{ \"field\": \"{}\" }}", cfg.display().replace("\n", "\\n")));
-                        (*cfm).insert(gameid, cfg);
+                        match (*cfm).get_mut(&gameid) {
+                            Some(cfg) => match cfg.withdraw_move(&player, Arc::new(ConnectFourMove{ data: column, })) {
+                                Ok(()) => {
+                                    answer = Some(format!("{{ \"field\": \"{}\" }}", cfg.display().replace("\n", "\\n")));
+                                },
+                                Err(e) => { failure = (error_status(&e), e.to_string()); },
+                            },
+                            None => { failure = unknown_game(gameid); },
+                        }
                     }
                 },
                 "eval" => {
                     if let Some(id) = &req.url.path().get(1) {
                         if let Ok(gameid) = (**id).parse::<u128>() {
-                            evaluation_clone = Some((*cfm).get(&gameid).unwrap().clone());
+                            evaluation_clone = (*cfm).get(&gameid).map(analysis_copy);
+                            if evaluation_clone.is_none() { failure = unknown_game(gameid); }
                         }
                     }   
                 },
@@ -138,10 +217,25 @@ impl Handler for ConnectFourHandler {
                     if let Some(id) = &req.url.path().get(1) {
                         if let Ok(gameid) = (**id).parse::<u128>() {
                             key = gameid;
-                            best_move_clone = Some((*cfm).get(&gameid).unwrap().clone());
+                            match (*cfm).get(&gameid) {
+                                // there is nothing to search for in a finished game
+                                Some(cfg) if *cfg.status() != GameStatus::InProgress => {
+                                    failure = (error_status(&GameError::GameOver), GameError::GameOver.to_string());
+                                },
+                                Some(cfg) => { best_move_clone = Some(analysis_copy(cfg)); },
+                                None => { failure = unknown_game(gameid); },
+                            }
                         }
                     }   
                 },
+                "analyze" => {
+                    if let Some(id) = &req.url.path().get(1) {
+                        if let Ok(gameid) = (**id).parse::<u128>() {
+                            analysis_clone = (*cfm).get(&gameid).map(analysis_copy);
+                            if analysis_clone.is_none() { failure = unknown_game(gameid); }
+                        }
+                    }
+                },
                 _ => (),
             }
         }
@@ -150,49 +244,50 @@ impl Handler for ConnectFourHandler {
         // so the possibly expensive calculations below do not inhibit other threads
         if let Some(cfclone) = evaluation_clone {
             if let (Some(_), Some(player), Some(column)) = readurl(&req) {
-                if let Ok(eval) = self.st.evaluate_move(Rc::new(RefCell::new(cfclone)), &player, Rc::new(ConnectFourMove{ data: column, })) {
-                    answer = Some(format!("{{ \"evaluation\": {} }}", eval));
+                match self.st.evaluate_move(Arc::new(RwLock::new(cfclone)), &player, Arc::new(ConnectFourMove{ data: column, })) {
+                    Ok(eval) => { answer = Some(format!("{{ \"evaluation\": {} }}", eval)); },
+                    Err(e) => { failure = (error_status(&e), e.to_string()); },
                 }
             }
         }
         if let Some(cfclone) = best_move_clone {
-            let lookahead;
-            {
-                let lam = self.lam.lock().unwrap();
-                lookahead = match (*lam).get(&key){ None => STARTN, Some(n) => *n };
-            }
             if let (Some(_), Some(player), _) = readurl(&req) {
                 let then = Instant::now();
-                if let (Some(mv), Some(score)) = self.st.find_best_move(Rc::new(RefCell::new(cfclone)), &player, lookahead, true) {
-                    answer = Some(format!("{{ \"bestmove\": {} }}", mv.data().to_usize()));
+                if let (Some(mv), Some(score)) = self.st.find_best_move_in_time(Arc::new(RwLock::new(cfclone)), &player, Duration::from_millis(TOLERABLE), true) {
+                    answer = Some(match mv.kind() {
+                        MoveKind::Pop => format!("{{ \"bestmove\": {}, \"pop\": true }}", mv.data().to_usize()),
+                        MoveKind::Drop => format!("{{ \"bestmove\": {} }}", mv.data().to_usize()),
+                    });
 
                     let now = Instant::now();
                     let tp = now.duration_since(then).as_secs() as u128 * 1000 + now.duration_since(then).subsec_millis() as u128;
                     
                     println!("{} best move scores {:?} pondering time was {}", key, score, tp);
-
-                    if tp < RESPITE {
-                        let mut lam = self.lam.lock().unwrap();
-                        (*lam).insert(key, lookahead+1);
-                        println!("{} set new lookahead: {}", key, (*lam).get(&key).unwrap());
-                    }
-                    if tp > TOLERABLE {
-                        let mut lam = self.lam.lock().unwrap();
-                        (*lam).insert(key, lookahead-1);
-                        println!("{} set new lookahead: {}", key, (*lam).get(&key).unwrap());
-                    }
-
+                } else {
+                    failure = (error_status(&GameError::GameOver), GameError::GameOver.to_string());
                 }
 
             }                    
         }
 
-        if let Some(line) = answer {
-            let mut response = Response::with((status::Ok, line.as_str()));
-            // allow all origins, so the service can be called from javascript
-            response.headers.set(AccessControlAllowOrigin::Any);
-            Ok(response)
-        } else { return Ok(Response::with(status::BadRequest)) }
+        if let Some(cfclone) = analysis_clone {
+            if let (Some(_), Some(player), _) = readurl(&req) {
+                let scores = self.st.analyze_position(Arc::new(RwLock::new(cfclone)), &player, ANALYSIS_DEPTH, true);
+                let scores:Vec<String> = scores.iter().map(|(column, score)| column_score(column, score)).collect();
+                answer = Some(format!("{{ \"scores\": [{}] }}", scores.join(", ")));
+            }
+        }
+
+        let mut response = match answer {
+            Some(line) => Response::with((status::Ok, line.as_str())),
+            None => {
+                let (code, message) = failure;
+                Response::with((code, format!("{{ \"error\": \"{}\" }}", message.replace("\"", "\\\""))))
+            },
+        };
+        // allow all origins, so the service can be called from javascript
+        response.headers.set(AccessControlAllowOrigin::Any);
+        Ok(response)
     }
 }
 
@@ -200,7 +295,6 @@ pub fn start_server(host:&str, port:i32, strategy:ConnectFourStrategy) -> iron::
     let server = Iron::new(ConnectFourHandler {
         zero: Instant::now(),
         cfm: Mutex::new(HashMap::new()),
-        lam: Mutex::new(HashMap::new()),
         st: strategy,
     }).http(format!("{}:{}", host, port)).unwrap();
     server
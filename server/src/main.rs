@@ -1,7 +1,32 @@
 extern crate server;
 extern crate game;
 
+use game::book::OpeningBook;
+use game::endgame::EndgameTable;
+use game::connectfour::{STANDARD_WIDTH,STANDARD_HEIGHT,STANDARD_WIN_LENGTH};
+use std::env;
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+
 fn main() {
-    let strategy = game::connectfour::ConnectFourStrategy::default();
+    // think with all cores
+    let nthreads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut strategy = game::connectfour::ConnectFourStrategy::new(nthreads);
+    let rules = (STANDARD_WIDTH, STANDARD_HEIGHT, STANDARD_WIN_LENGTH);
+    // an opening book may be given, as written by the game's book command
+    strategy.book = env::args().nth(1).and_then(|path| {
+        match OpeningBook::read(Path::new(&path), rules) {
+            Ok(book) => Some(Arc::new(book)),
+            Err(e) => { println!("playing without book {}: {}", path, e); None },
+        }
+    });
+    // and an endgame table after it, as written by the game's endgame command
+    strategy.endgame = env::args().nth(2).and_then(|path| {
+        match EndgameTable::read(Path::new(&path), rules) {
+            Ok(table) => Some(Arc::new(table)),
+            Err(e) => { println!("playing without endgame table {}: {}", path, e); None },
+        }
+    });
     server::start_server("localhost", 8095, strategy);
 }
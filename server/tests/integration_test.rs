@@ -5,9 +5,14 @@ extern crate hyper;
 extern crate regex;
 
 use server::start_server;
-use game::connectfour::ConnectFourStrategy;
+use game::book::{OpeningBook,PositionTable};
+use game::bruteforce::{StoredGame,write_store};
+use game::connectfour::{Column,ConnectFour,ConnectFourStrategy};
+use game::endgame::EndgameTable;
+use game::generic::{Player,Score};
 use iron::Listening;
 use std::io::Read;
+use std::sync::Arc;
 use regex::Regex;
 
 #[test]
@@ -16,8 +21,13 @@ fn it_works() {
     let client = hyper::Client::new();
 
     fn check_response<'a>(q:&str, a:&str, s:&TestServer, c: &hyper::Client) -> Vec<String>  {
+        check_status(q, hyper::status::StatusCode::Ok, a, s, c)
+    }
+
+    fn check_status(q:&str, st:hyper::status::StatusCode, a:&str, s:&TestServer, c: &hyper::Client) -> Vec<String>  {
         let url = format!("{}/{}", s.url(), q);
         let mut response = c.get(&url).send().unwrap();
+        assert_eq!(response.status, st);
         let mut rs = String::new();
         response.read_to_string(&mut rs).unwrap();
 
@@ -33,6 +43,92 @@ fn it_works() {
     let gameid2 = check_response("new", "[{] \"field\": \"-{6}([\\\\]n){8}-{6}\", \"gameid\": ([0-9]+) [}]", &server, &client).pop().unwrap();
     check_response(format!("move/{}/white/4", gameid1).as_str(), "[{] \"field\": \"-{6}([\\\\]n){5}o([\\\\]n){3}-{6}\" [}]", &server, &client);
     check_response(format!("move/{}/black/5", gameid2).as_str(), "[{] \"field\": \"-{6}([\\\\]n){6}x([\\\\]n){2}-{6}\" [}]", &server, &client);
+    // four on top of each other
+    for _ in 0..2 {
+        check_response(format!("move/{}/white/0", gameid2).as_str(), "^[{] \"field\": \"[^\"]*\" [}]$", &server, &client);
+        check_response(format!("move/{}/black/5", gameid2).as_str(), "^[{] \"field\": \"[^\"]*\" [}]$", &server, &client);
+    }
+    check_response(format!("move/{}/white/0", gameid2).as_str(), "^[{] \"field\": \"[^\"]*\" [}]$", &server, &client);
+    check_response(format!("move/{}/black/5", gameid2).as_str(),
+        "^[{] \"field\": \"-{6}([\\\\]n)ooo([\\\\]n){5}xxxx([\\\\]n){2}-{6}\", \"lines\": \\[\\[\\[5, 0\\], \\[5, 1\\], \\[5, 2\\], \\[5, 3\\]\\]\\], \"moves\": \"6161616\" [}]$", &server, &client);
+    // no moves after the game is over
+    check_status(format!("move/{}/white/0", gameid2).as_str(), hyper::status::StatusCode::Conflict,
+        "^[{] \"error\": \"game is over\" [}]$", &server, &client);
+    // no moves out of turn
+    check_response(format!("move/{}/black/1", gameid1).as_str(), "^[{] \"field\": \"[^\"]*\" [}]$", &server, &client);
+    check_status(format!("move/{}/black/1", gameid1).as_str(), hyper::status::StatusCode::Conflict,
+        "^[{] \"error\": \"it is the other player's turn\" [}]$", &server, &client);
+    // no such columns, stones or games
+    check_status(format!("move/{}/white/7", gameid1).as_str(), hyper::status::StatusCode::BadRequest,
+        "^[{] \"error\": \"there is no column 7\" [}]$", &server, &client);
+    check_status(format!("withdraw/{}/white/6", gameid1).as_str(), hyper::status::StatusCode::Conflict,
+        "^[{] \"error\": \"only the last move can be taken back\" [}]$", &server, &client);
+    check_status(format!("withdraw/{}/white/4", gameid1).as_str(), hyper::status::StatusCode::Conflict,
+        "^[{] \"error\": \"only the last move can be taken back\" [}]$", &server, &client);
+    check_response(format!("withdraw/{}/black/1", gameid1).as_str(), "^[{] \"field\": \"[^\"]*\" [}]$", &server, &client);
+    check_response(format!("move/{}/black/1", gameid1).as_str(), "^[{] \"field\": \"[^\"]*\" [}]$", &server, &client);
+    check_status("move/123456789123456789/white/0", hyper::status::StatusCode::NotFound,
+        "^[{] \"error\": \"there is no game 123456789123456789\" [}]$", &server, &client);
+    check_status("best/123456789123456789/white", hyper::status::StatusCode::NotFound,
+        "^[{] \"error\": \"there is no game 123456789123456789\" [}]$", &server, &client);
+    // boards of other dimensions and rules
+    let gameid3 = check_response("new/9/7", "[{] \"field\": \"-{6}([\\\\]n){10}-{6}\", \"gameid\": ([0-9]+) [}]", &server, &client).pop().unwrap();
+    check_response(format!("move/{}/white/8", gameid3).as_str(), "[{] \"field\": \"-{6}([\\\\]n){9}o([\\\\]n)-{6}\" [}]", &server, &client);
+    check_status("new/17/6", hyper::status::StatusCode::BadRequest,
+        "^[{] \"error\": \"there is no 17x6 board\" [}]$", &server, &client);
+    let gameid4 = check_response("new/5/4/3", "[{] \"field\": \"-{6}([\\\\]n){6}-{6}\", \"gameid\": ([0-9]+) [}]", &server, &client).pop().unwrap();
+    for column in 0..2 {
+        check_response(format!("move/{}/white/{}", gameid4, column).as_str(), "^[{] \"field\": \"[^\"]*\" [}]$", &server, &client);
+        check_response(format!("move/{}/black/{}", gameid4, column).as_str(), "^[{] \"field\": \"[^\"]*\" [}]$", &server, &client);
+    }
+    check_response(format!("move/{}/white/2", gameid4).as_str(),
+        "^[{] \"field\": \"[^\"]*\", \"lines\": \\[\\[\\[0, 0\\], \\[1, 0\\], \\[2, 0\\]\\]\\], \"moves\": \"11223\" [}]$", &server, &client);
+    check_status("new/5/4/6", hyper::status::StatusCode::BadRequest,
+        "^[{] \"error\": \"there is no connect 6 on this board\" [}]$", &server, &client);
+    // Pop Out
+    check_status(format!("pop/{}/white/3", gameid1).as_str(), hyper::status::StatusCode::BadRequest,
+        "^[{] \"error\": \"stones cannot be popped in this game\" [}]$", &server, &client);
+    let gameid5 = check_response("new/popout", "[{] \"field\": \"-{6}([\\\\]n){8}-{6}\", \"gameid\": ([0-9]+) [}]", &server, &client).pop().unwrap();
+    check_response(format!("move/{}/white/0", gameid5).as_str(), "^[{] \"field\": \"[^\"]*\" [}]$", &server, &client);
+    check_response(format!("move/{}/black/0", gameid5).as_str(), "^[{] \"field\": \"[^\"]*\" [}]$", &server, &client);
+    check_response(format!("pop/{}/white/0", gameid5).as_str(), "^[{] \"field\": \"-{6}([\\\\]n)x([\\\\]n){7}-{6}\" [}]$", &server, &client);
+    check_status(format!("pop/{}/black/1", gameid5).as_str(), hyper::status::StatusCode::BadRequest,
+        "^[{] \"error\": \"column is empty\" [}]$", &server, &client);
+    check_status(format!("pop/{}/white/0", gameid5).as_str(), hyper::status::StatusCode::Conflict,
+        "^[{] \"error\": \"it is the other player's turn\" [}]$", &server, &client);
+    check_response(format!("best/{}/black", gameid5).as_str(), "^[{] \"bestmove\": [0-6](?:, \"pop\": true)? [}]$", &server, &client);
+    // games set up by their moves
+    check_response("new?moves=4453", "[{] \"field\": \"-{6}([\\\\]n){3}x([\\\\]n)ox([\\\\]n)o([\\\\]n){3}-{6}\", \"gameid\": ([0-9]+) [}]", &server, &client);
+    check_status("new?moves=44x", hyper::status::StatusCode::BadRequest,
+        "^[{] \"error\": \"line 1, column 3: unknown move 'x'\" [}]$", &server, &client);
+    check_status("new?moves=1111111", hyper::status::StatusCode::BadRequest,
+        "^[{] \"error\": \"line 1, column 7: column is full\" [}]$", &server, &client);
+    // nothing to search for in a drawn game
+    let gameid7 = check_response("new/3/2/3?moves=123123", "[{] \"field\": \"[^\"]*\", \"gameid\": ([0-9]+) [}]", &server, &client).pop().unwrap();
+    check_status(format!("best/{}/white", gameid7).as_str(), hyper::status::StatusCode::Conflict,
+        "^[{] \"error\": \"game is over\" [}]$", &server, &client);
+    // a score for each of the seven columns
+    check_response(format!("analyze/{}/white", gameid1).as_str(),
+        "^[{] \"scores\": \\[([{] \"column\": [0-6], \"score\": \"undecided\", \"value\": [0-9.]+ [}](, )?){7}\\] [}]$", &server, &client);
+
+    // a book that knows the first move only
+    let path = std::env::temp_dir().join(format!("connectfour-server-book-{}", std::process::id()));
+    let first_move = StoredGame { hash: 0, player: Player::White, score: Score::Won(40), column: Some(Column::Four) };
+    write_store(&path, (7, 6, 4), &[first_move]).unwrap();
+    let book = OpeningBook::read(&path, (7, 6, 4)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let server = TestServer::with_strategy(ConnectFourStrategy { book: Some(Arc::new(book)), ..ConnectFourStrategy::default() });
+    let gameid6 = check_response("new", "[{] \"field\": \"-{6}([\\\\]n){8}-{6}\", \"gameid\": ([0-9]+) [}]", &server, &client).pop().unwrap();
+    check_response(format!("best/{}/white", gameid6).as_str(), "^[{] \"bestmove\": 3 [}]$", &server, &client);
+
+    // an endgame table that knows every position of a small board
+    let table = EndgameTable::generate((3, 3, 3), 0).unwrap();
+    let mut game = ConnectFour::with_rules(3, 3, 3).unwrap();
+    game.play_move_string("2").unwrap();
+    let (column, _) = table.probe(&game, &Player::Black).unwrap();
+    let server = TestServer::with_strategy(ConnectFourStrategy { endgame: Some(Arc::new(table)), ..ConnectFourStrategy::default() });
+    let gameid8 = check_response("new/3/3/3?moves=2", "[{] \"field\": \"[^\"]*\", \"gameid\": ([0-9]+) [}]", &server, &client).pop().unwrap();
+    check_response(format!("best/{}/black", gameid8).as_str(), format!("^[{{] \"bestmove\": {} [}}]$", column.to_usize()).as_str(), &server, &client);
 }
 
 struct TestServer(Listening);
@@ -42,6 +138,10 @@ impl TestServer {
         TestServer(start_server("127.0.0.1", 0, ConnectFourStrategy::default()))
     }
 
+    fn with_strategy(strategy: ConnectFourStrategy) -> TestServer {
+        TestServer(start_server("127.0.0.1", 0, strategy))
+    }
+
     fn url(&self) -> String {
         format!("http://{}:{}", self.0.socket.ip(), self.0.socket.port())
     }